@@ -0,0 +1,134 @@
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, near_bindgen, require, AccountId};
+
+use crate::events::FtApprove;
+use crate::pause::PausableFeature;
+use crate::{Contract, ContractExt};
+
+const MAX_ALLOWANCES_PER_ACCOUNT: usize = 20;
+
+impl Contract {
+    /// Adds or removes the spender in the list of the spenders the owner has granted allowances to.
+    fn internal_update_allowance_spenders(
+        &mut self,
+        owner_id: &AccountId,
+        spender_id: &AccountId,
+        is_granted: bool,
+    ) {
+        let mut spender_ids = self.allowance_spenders.get(owner_id).unwrap_or_default();
+        let position = spender_ids
+            .iter()
+            .position(|account_id| account_id == spender_id);
+        match (position, is_granted) {
+            (None, true) => {
+                require!(
+                    spender_ids.len() < MAX_ALLOWANCES_PER_ACCOUNT,
+                    "Too many allowances"
+                );
+                spender_ids.push(spender_id.clone());
+            }
+            (Some(index), false) => {
+                spender_ids.swap_remove(index);
+            }
+            _ => return,
+        }
+        if spender_ids.is_empty() {
+            self.allowance_spenders.remove(owner_id);
+        } else {
+            self.allowance_spenders.insert(owner_id, &spender_ids);
+        }
+    }
+
+    /// Revokes all the allowances granted by the unregistered owner, refunding their storage.
+    pub(crate) fn internal_remove_allowances(&mut self, owner_id: &AccountId) {
+        let initial_storage_usage = env::storage_usage();
+        let Some(spender_ids) = self.allowance_spenders.remove(owner_id) else {
+            return;
+        };
+        for spender_id in spender_ids {
+            self.allowances.remove(&(owner_id.clone(), spender_id));
+        }
+        self.internal_refund_released_storage(owner_id, initial_storage_usage);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Allows `spender_id` to transfer up to `amount` of the caller's tokens with
+    /// `ft_transfer_from`, replacing the previous allowance. Zero `amount` revokes the allowance.
+    /// An account can grant up to `MAX_ALLOWANCES_PER_ACCOUNT` (20) allowances.
+    ///
+    /// The caller must attach a deposit covering the storage of a new allowance (the unused deposit
+    /// is refunded); the storage of a revoked allowance is refunded to the caller.
+    #[payable]
+    pub fn ft_approve(&mut self, spender_id: AccountId, amount: U128) {
+        require!(
+            env::attached_deposit() > 0,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        self.assert_not_paused(PausableFeature::Transfers);
+        let owner_id = env::predecessor_account_id();
+        require!(
+            owner_id != spender_id,
+            "Owner and spender should be different"
+        );
+        self.token.internal_unwrap_balance_of(&owner_id);
+
+        let initial_storage_usage = env::storage_usage();
+        let key = (owner_id.clone(), spender_id.clone());
+        if amount.0 > 0 {
+            self.allowances.insert(&key, &amount.0);
+        } else {
+            self.allowances.remove(&key);
+        }
+        self.internal_update_allowance_spenders(&owner_id, &spender_id, amount.0 > 0);
+        self.internal_charge_storage_deposit(initial_storage_usage);
+        FtApprove {
+            owner_id: &owner_id,
+            spender_id: &spender_id,
+            amount: &amount,
+        }
+        .emit();
+    }
+
+    /// Transfers `amount` of tokens from `owner_id` to `receiver_id` on behalf of the caller,
+    /// spending the allowance granted by `owner_id` with `ft_approve`.
+    /// Exactly 1 yoctoNEAR must be attached.
+    #[payable]
+    pub fn ft_transfer_from(
+        &mut self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        self.assert_not_paused(PausableFeature::Transfers);
+        let spender_id = env::predecessor_account_id();
+        let key = (owner_id.clone(), spender_id.clone());
+        let allowance = self.allowances.get(&key).unwrap_or(0);
+        let Some(new_allowance) = allowance.checked_sub(amount.0) else {
+            env::panic_str("The amount exceeds the allowance");
+        };
+        if new_allowance > 0 {
+            self.allowances.insert(&key, &new_allowance);
+        } else {
+            // Refund the storage of the used up allowance to the owner who paid for it.
+            let initial_storage_usage = env::storage_usage();
+            self.allowances.remove(&key);
+            self.internal_update_allowance_spenders(&owner_id, &spender_id, false);
+            self.internal_refund_released_storage(&owner_id, initial_storage_usage);
+        }
+        self.internal_claim_vested_before_spending(&owner_id);
+        self.token
+            .internal_transfer(&owner_id, &receiver_id, amount.into(), memo);
+    }
+
+    /// Returns the amount of the `owner_id` tokens `spender_id` can transfer.
+    pub fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128 {
+        self.allowances
+            .get(&(owner_id, spender_id))
+            .unwrap_or(0)
+            .into()
+    }
+}
@@ -0,0 +1,213 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::events::{EscrowCreated, EscrowSettled};
+use crate::fungible_token::seasons::SeasonId;
+use crate::internal::{insert_into_index, remove_from_index};
+use crate::pause::PausableFeature;
+use crate::{Contract, ContractExt};
+
+const MAX_ESCROWS_PER_ACCOUNT: usize = 50;
+const MAX_ESCROW_MEMO_LENGTH: usize = 256;
+
+/// Tokens the payer locks for the payee until the payer confirms the deal or the arbiter (a
+/// reward operator) decides the dispute. The tokens are refunded to the payer after the timeout.
+///
+/// The locked tokens are held by the contract account until they are settled.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Escrow {
+    pub id: U64,
+    pub payer_id: AccountId,
+    pub payee_id: AccountId,
+    pub arbiter_id: AccountId,
+    pub amount: U128,
+    pub memo: Option<String>,
+    /// Nanoseconds since the Unix epoch.
+    pub created_at: U64,
+    /// Anyone can refund the tokens to the payer after this block timestamp (in nanoseconds).
+    pub expires_at: U64,
+    /// The season the tokens were locked in.
+    pub season_id: SeasonId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowOutcome {
+    /// The tokens are paid to the payee.
+    Released,
+    /// The tokens are returned to the payer.
+    Refunded,
+}
+
+impl Contract {
+    fn internal_unwrap_escrow(&self, escrow_id: U64) -> Escrow {
+        self.escrows
+            .get(&escrow_id.0)
+            .unwrap_or_else(|| env::panic_str("The escrow does not exist"))
+    }
+
+    /// Pays out the escrowed tokens according to the outcome and removes the escrow, refunding its
+    /// storage to the payer. The tokens of a frozen payer cannot be released to the payee.
+    fn internal_settle_escrow(&mut self, escrow: Escrow, outcome: EscrowOutcome) {
+        let (account_id, memo) = match outcome {
+            EscrowOutcome::Released => {
+                self.token.internal_assert_not_frozen(&escrow.payer_id);
+                (&escrow.payee_id, "escrow release")
+            }
+            EscrowOutcome::Refunded => (&escrow.payer_id, "escrow refund"),
+        };
+        let initial_storage_usage = env::storage_usage();
+        self.escrows.remove(&escrow.id.0);
+        remove_from_index(&mut self.account_escrows, &escrow.payer_id, escrow.id.0);
+        remove_from_index(&mut self.received_escrows, &escrow.payee_id, escrow.id.0);
+        remove_from_index(
+            &mut self.arbitrated_escrows,
+            &escrow.arbiter_id,
+            escrow.id.0,
+        );
+        self.internal_refund_released_storage(&escrow.payer_id, initial_storage_usage);
+
+        self.internal_unlock_tokens(account_id, escrow.amount.0, escrow.season_id, memo);
+        EscrowSettled {
+            escrow_id: escrow.id,
+            outcome: &outcome,
+            settled_by: &env::predecessor_account_id(),
+        }
+        .emit();
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Locks `amount` of the caller's tokens for `payee_id` with `arbiter_id` (a reward operator)
+    /// deciding disputes. The tokens can be refunded to the caller once `timeout` nanoseconds
+    /// pass. An account can pay into up to 50 escrows at a time, and the memo is limited to 256
+    /// bytes.
+    ///
+    /// The caller must attach a deposit covering the storage of the escrow (the unused deposit is
+    /// refunded); the storage is refunded to the caller once the escrow is settled.
+    ///
+    /// Returns the escrow id.
+    #[payable]
+    pub fn create_escrow(
+        &mut self,
+        payee_id: AccountId,
+        arbiter_id: AccountId,
+        amount: U128,
+        timeout: U64,
+        memo: Option<String>,
+    ) -> U64 {
+        require!(
+            env::attached_deposit() > 0,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        self.assert_not_paused(PausableFeature::Transfers);
+        require!(
+            !matches!(&memo, Some(memo) if memo.len() > MAX_ESCROW_MEMO_LENGTH),
+            "The memo is too long"
+        );
+        let payer_id = env::predecessor_account_id();
+        require!(payer_id != payee_id, "Payer and payee should be different");
+        require!(
+            self.reward_operators.contains(&arbiter_id),
+            "The arbiter should be a reward operator"
+        );
+        require!(
+            arbiter_id != payer_id && arbiter_id != payee_id,
+            "The arbiter should not be a party of the escrow"
+        );
+        self.token.internal_unwrap_balance_of(&payee_id);
+        let mut payer_escrow_ids = self.account_escrows.get(&payer_id).unwrap_or_default();
+        require!(
+            payer_escrow_ids.len() < MAX_ESCROWS_PER_ACCOUNT,
+            "Too many active escrows"
+        );
+        self.internal_claim_vested_before_spending(&payer_id);
+        let initial_storage_usage = env::storage_usage();
+        self.internal_lock_tokens(&payer_id, amount.0, "escrow deposit");
+
+        let created_at = env::block_timestamp();
+        let escrow = Escrow {
+            id: self.next_escrow_id.into(),
+            payer_id,
+            payee_id,
+            arbiter_id,
+            amount,
+            memo,
+            created_at: created_at.into(),
+            expires_at: created_at.saturating_add(timeout.0).into(),
+            season_id: self.token.current_season_id(),
+        };
+        self.next_escrow_id += 1;
+        self.escrows.insert(&escrow.id.0, &escrow);
+        payer_escrow_ids.push(escrow.id.0);
+        self.account_escrows
+            .insert(&escrow.payer_id, &payer_escrow_ids);
+        insert_into_index(&mut self.received_escrows, &escrow.payee_id, escrow.id.0);
+        insert_into_index(
+            &mut self.arbitrated_escrows,
+            &escrow.arbiter_id,
+            escrow.id.0,
+        );
+        self.internal_charge_storage_deposit(initial_storage_usage);
+        EscrowCreated { escrow: &escrow }.emit();
+        escrow.id
+    }
+
+    /// Confirms the deal, paying the escrowed tokens to the payee. Can only be called by the
+    /// payer while they are not frozen.
+    pub fn release_escrow(&mut self, escrow_id: U64) {
+        self.assert_not_paused(PausableFeature::Transfers);
+        let escrow = self.internal_unwrap_escrow(escrow_id);
+        require!(
+            env::predecessor_account_id() == escrow.payer_id,
+            "Can only be called by the payer"
+        );
+        self.internal_settle_escrow(escrow, EscrowOutcome::Released);
+    }
+
+    /// Decides the dispute, paying the escrowed tokens to the payee or returning them to the
+    /// payer. Can only be called by the arbiter while they are a reward operator.
+    pub fn resolve_escrow(&mut self, escrow_id: U64, outcome: EscrowOutcome) {
+        self.assert_not_paused(PausableFeature::Transfers);
+        let escrow = self.internal_unwrap_escrow(escrow_id);
+        require!(
+            env::predecessor_account_id() == escrow.arbiter_id,
+            "Can only be called by the arbiter"
+        );
+        self.assert_reward_operator();
+        self.internal_settle_escrow(escrow, outcome);
+    }
+
+    /// Returns the escrowed tokens to the payer. Can be called by the payee at any time, or by
+    /// anyone once the escrow has expired.
+    pub fn refund_escrow(&mut self, escrow_id: U64) {
+        self.assert_not_paused(PausableFeature::Transfers);
+        let escrow = self.internal_unwrap_escrow(escrow_id);
+        require!(
+            env::predecessor_account_id() == escrow.payee_id
+                || env::block_timestamp() > escrow.expires_at.0,
+            "The escrow has not expired yet"
+        );
+        self.internal_settle_escrow(escrow, EscrowOutcome::Refunded);
+    }
+
+    pub fn escrow(&self, escrow_id: U64) -> Option<Escrow> {
+        self.escrows.get(&escrow_id.0)
+    }
+
+    /// Returns the active escrows the account is the payer, the payee or the arbiter of.
+    pub fn escrows_of(&self, account_id: AccountId) -> Vec<Escrow> {
+        let mut escrow_ids = self.account_escrows.get(&account_id).unwrap_or_default();
+        escrow_ids.extend(self.received_escrows.get(&account_id).unwrap_or_default());
+        escrow_ids.extend(self.arbitrated_escrows.get(&account_id).unwrap_or_default());
+        escrow_ids
+            .into_iter()
+            .filter_map(|escrow_id| self.escrows.get(&escrow_id))
+            .collect()
+    }
+}
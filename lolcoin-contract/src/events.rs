@@ -0,0 +1,447 @@
+//! Lolcoin-specific events that complement the NEP-141 events (`FtMint`, `FtTransfer`, `FtBurn`)
+//! emitted through `near_contract_standards::fungible_token::events`.
+//!
+//! The events follow the NEP-297 format:
+//! <https://github.com/near/NEPs/blob/master/specs/Standards/EventsFormat.md>
+//!
+//! These events can be logged by calling `.emit()` on them.
+
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, AccountId};
+
+use crate::escrows::{Escrow, EscrowOutcome};
+use crate::fungible_token::seasons::SeasonId;
+use crate::fungible_token::snapshots::SnapshotId;
+use crate::operators::RewardOperatorLimits;
+use crate::pause::PauseFlags;
+use crate::polls::Poll;
+use crate::proposals::{MultisigPolicy, ProposalOutcome};
+use crate::schedules::RewardSchedule;
+use crate::sponsorship::{StorageSponsor, StorageSponsorshipPool};
+use crate::staking::{Stake, StakingTerm};
+use crate::streams::Stream;
+use crate::vesting::VestingGrant;
+
+const LOLCOIN_STANDARD_NAME: &str = "lolcoin";
+const LOLCOIN_STANDARD_VERSION: &str = "1.0.0";
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+struct LolcoinEvent<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event_kind: LolcoinEventKind<'a>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+enum LolcoinEventKind<'a> {
+    OwnerChanged(&'a [OwnerChanged<'a>]),
+    RewardOperatorAdded(&'a [RewardOperatorAdded<'a>]),
+    RewardOperatorRemoved(&'a [RewardOperatorRemoved<'a>]),
+    RewardOperatorLimitsUpdated(&'a [RewardOperatorLimitsUpdated<'a>]),
+    FtMetadataUpdated(&'a [FtMetadataUpdated<'a>]),
+    PauseFlagsUpdated(&'a [PauseFlagsUpdated<'a>]),
+    AccountFrozen(&'a [AccountFrozen<'a>]),
+    AccountUnfrozen(&'a [AccountUnfrozen<'a>]),
+    VestingGrantCreated(&'a [VestingGrantCreated<'a>]),
+    SeasonClosed(&'a [SeasonClosed<'a>]),
+    FtApprove(&'a [FtApprove<'a>]),
+    MultisigPolicyUpdated(&'a [MultisigPolicyUpdated<'a>]),
+    RewardProposalCreated(&'a [RewardProposalCreated<'a>]),
+    RewardProposalApproved(&'a [RewardProposalApproved<'a>]),
+    RewardProposalRejected(&'a [RewardProposalRejected<'a>]),
+    RewardProposalFinalized(&'a [RewardProposalFinalized<'a>]),
+    AccountClosed(&'a [AccountClosed<'a>]),
+    SnapshotTaken(&'a [SnapshotTaken<'a>]),
+    PollCreated(&'a [PollCreated<'a>]),
+    PollVoted(&'a [PollVoted<'a>]),
+    StorageSponsored(&'a [StorageSponsored<'a>]),
+    StorageSponsorshipPoolUpdated(&'a [StorageSponsorshipPoolUpdated<'a>]),
+    StakingTermsUpdated(&'a [StakingTermsUpdated<'a>]),
+    Staked(&'a [Staked<'a>]),
+    Unstaked(&'a [Unstaked<'a>]),
+    RewardScheduleCreated(&'a [RewardScheduleCreated<'a>]),
+    RewardScheduleCanceled(&'a [RewardScheduleCanceled<'a>]),
+    StreamCreated(&'a [StreamCreated<'a>]),
+    StreamUpdated(&'a [StreamUpdated<'a>]),
+    StreamCanceled(&'a [StreamCanceled<'a>]),
+    EscrowCreated(&'a [EscrowCreated<'a>]),
+    EscrowSettled(&'a [EscrowSettled<'a>]),
+}
+
+impl LolcoinEventKind<'_> {
+    fn emit(self) {
+        let event = LolcoinEvent {
+            standard: LOLCOIN_STANDARD_NAME,
+            version: LOLCOIN_STANDARD_VERSION,
+            event_kind: self,
+        };
+        // Events cannot fail to serialize so fine to panic on error
+        let event_json = near_sdk::serde_json::to_string(&event).unwrap_or_else(|_| env::abort());
+        env::log_str(&format!("EVENT_JSON:{}", event_json));
+    }
+}
+
+/// Implements `.emit()` for an event data struct, logging it as a single-entry event of the given
+/// kind.
+macro_rules! impl_emit {
+    ($event:ident) => {
+        impl $event<'_> {
+            /// Logs the event to the host. This is required to ensure that the event is triggered
+            /// and to consume the event.
+            pub fn emit(self) {
+                LolcoinEventKind::$event(&[self]).emit()
+            }
+        }
+    };
+}
+
+/// Data to log when the contract ownership is transferred.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnerChanged<'a> {
+    pub old_owner_id: &'a AccountId,
+    pub new_owner_id: &'a AccountId,
+}
+impl_emit!(OwnerChanged);
+
+/// Data to log when an account is granted the reward operator role.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardOperatorAdded<'a> {
+    pub operator_id: &'a AccountId,
+}
+impl_emit!(RewardOperatorAdded);
+
+/// Data to log when the reward operator role is revoked from an account.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardOperatorRemoved<'a> {
+    pub operator_id: &'a AccountId,
+}
+impl_emit!(RewardOperatorRemoved);
+
+/// Data to log when the minting limits of a reward operator are changed. `limits` is `None` when
+/// the limits are lifted.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardOperatorLimitsUpdated<'a> {
+    pub operator_id: &'a AccountId,
+    pub limits: Option<&'a RewardOperatorLimits>,
+}
+impl_emit!(RewardOperatorLimitsUpdated);
+
+/// Data to log when the token metadata is changed. `updated_fields` is `["*"]` when the metadata
+/// is replaced as a whole.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtMetadataUpdated<'a> {
+    pub updated_fields: &'a [&'a str],
+}
+impl_emit!(FtMetadataUpdated);
+
+/// Data to log when the contract features are paused or unpaused.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseFlagsUpdated<'a> {
+    #[serde(flatten)]
+    pub pause_flags: &'a PauseFlags,
+}
+impl_emit!(PauseFlagsUpdated);
+
+/// Data to log when the balance of an account is frozen.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountFrozen<'a> {
+    pub account_id: &'a AccountId,
+    pub reason: &'a str,
+}
+impl_emit!(AccountFrozen);
+
+/// Data to log when the balance of an account is unfrozen.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountUnfrozen<'a> {
+    pub account_id: &'a AccountId,
+    pub reason: &'a str,
+}
+impl_emit!(AccountUnfrozen);
+
+/// Data to log when an account is granted vesting tokens. The tokens are minted (see `FtMint`)
+/// once they are unlocked and claimed.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingGrantCreated<'a> {
+    pub account_id: &'a AccountId,
+    #[serde(flatten)]
+    pub grant: &'a VestingGrant,
+}
+impl_emit!(VestingGrantCreated);
+
+/// Data to log when a season is closed and the next one is opened. The conversion of the
+/// `outstanding_supply` left from the closed season is logged as `FtBurn` and `FtMint` events
+/// once the balances are settled.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeasonClosed<'a> {
+    pub season_id: SeasonId,
+    pub conversion_rate_bps: u32,
+    pub outstanding_supply: &'a U128,
+    pub next_season_id: SeasonId,
+}
+impl_emit!(SeasonClosed);
+
+/// Data to log when an account sets the allowance of a spender. Zero `amount` means the allowance
+/// is revoked.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtApprove<'a> {
+    pub owner_id: &'a AccountId,
+    pub spender_id: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(FtApprove);
+
+/// Data to log when the policy for approving large rewards is changed. `policy` is `None` when the
+/// policy is lifted.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MultisigPolicyUpdated<'a> {
+    pub policy: Option<&'a MultisigPolicy>,
+}
+impl_emit!(MultisigPolicyUpdated);
+
+/// Data to log when a reward above the multisig threshold is proposed.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardProposalCreated<'a> {
+    pub proposal_id: U64,
+    pub proposer_id: &'a AccountId,
+    pub total_amount: &'a U128,
+    pub expires_at: U64,
+}
+impl_emit!(RewardProposalCreated);
+
+/// Data to log when a reward operator approves a reward proposal.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardProposalApproved<'a> {
+    pub proposal_id: U64,
+    pub operator_id: &'a AccountId,
+}
+impl_emit!(RewardProposalApproved);
+
+/// Data to log when a reward operator rejects a reward proposal.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardProposalRejected<'a> {
+    pub proposal_id: U64,
+    pub operator_id: &'a AccountId,
+}
+impl_emit!(RewardProposalRejected);
+
+/// Data to log when a reward proposal is executed (see `FtMint`), rejected or expired.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardProposalFinalized<'a> {
+    pub proposal_id: U64,
+    pub outcome: &'a ProposalOutcome,
+}
+impl_emit!(RewardProposalFinalized);
+
+/// Data to log when a holder closes their account, transferring the remaining `balance` to the
+/// beneficiary (see `FtTransfer`) and getting the storage deposit back.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountClosed<'a> {
+    pub account_id: &'a AccountId,
+    pub beneficiary_id: &'a AccountId,
+    pub balance: &'a U128,
+}
+impl_emit!(AccountClosed);
+
+/// Data to log when a snapshot of the balances is taken.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SnapshotTaken<'a> {
+    pub snapshot_id: SnapshotId,
+    pub total_supply: &'a U128,
+}
+impl_emit!(SnapshotTaken);
+
+/// Data to log when a poll is created.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PollCreated<'a> {
+    #[serde(flatten)]
+    pub poll: &'a Poll,
+}
+impl_emit!(PollCreated);
+
+/// Data to log when a holder votes in a poll or changes their vote.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PollVoted<'a> {
+    pub poll_id: U64,
+    pub voter_id: &'a AccountId,
+    pub option_index: u32,
+    pub weight: &'a U128,
+}
+impl_emit!(PollVoted);
+
+/// Data to log when an account is registered, recording who paid for its storage.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageSponsored<'a> {
+    pub account_id: &'a AccountId,
+    pub sponsor: &'a StorageSponsor,
+}
+impl_emit!(StorageSponsored);
+
+/// Data to log when the cap of the registrations sponsored by the contract pool is changed.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageSponsorshipPoolUpdated<'a> {
+    #[serde(flatten)]
+    pub pool: &'a StorageSponsorshipPool,
+}
+impl_emit!(StorageSponsorshipPoolUpdated);
+
+/// Data to log when the staking terms are changed.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakingTermsUpdated<'a> {
+    pub terms: &'a [StakingTerm],
+}
+impl_emit!(StakingTermsUpdated);
+
+/// Data to log when a holder stakes tokens. The staked tokens are transferred to the contract
+/// account (see `FtTransfer`).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Staked<'a> {
+    pub account_id: &'a AccountId,
+    #[serde(flatten)]
+    pub stake: &'a Stake,
+}
+impl_emit!(Staked);
+
+/// Data to log when a holder unstakes tokens. The `amount` is transferred back from the contract
+/// account (see `FtTransfer`) and the `interest` is minted (see `FtMint`).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Unstaked<'a> {
+    pub account_id: &'a AccountId,
+    pub stake_id: U64,
+    pub amount: &'a U128,
+    pub interest: &'a U128,
+}
+impl_emit!(Unstaked);
+
+/// Data to log when a recurring reward is scheduled. The installments are logged as `FtMint`
+/// events once they are paid out.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardScheduleCreated<'a> {
+    #[serde(flatten)]
+    pub schedule: &'a RewardSchedule,
+}
+impl_emit!(RewardScheduleCreated);
+
+/// Data to log when a reward schedule is canceled before paying out all its installments.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardScheduleCanceled<'a> {
+    pub schedule_id: U64,
+    pub recipient_id: &'a AccountId,
+}
+impl_emit!(RewardScheduleCanceled);
+
+/// Data to log when a holder starts streaming tokens. The deposit is transferred to the contract
+/// account and on to the receiver as it is withdrawn (see `FtTransfer`).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StreamCreated<'a> {
+    #[serde(flatten)]
+    pub stream: &'a Stream,
+}
+impl_emit!(StreamCreated);
+
+/// Data to log when a stream is paused or resumed.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StreamUpdated<'a> {
+    pub stream_id: U64,
+    pub receiver_id: &'a AccountId,
+    pub paused: bool,
+}
+impl_emit!(StreamUpdated);
+
+/// Data to log when a stream is canceled, paying out the `withdrawn` tokens to the receiver and
+/// the `refund` to the sender.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StreamCanceled<'a> {
+    pub stream_id: U64,
+    pub withdrawn: &'a U128,
+    pub refund: &'a U128,
+}
+impl_emit!(StreamCanceled);
+
+/// Data to log when a payer locks tokens in an escrow. The tokens are transferred to the contract
+/// account and on to the payee or back to the payer once the escrow is settled (see
+/// `FtTransfer`).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EscrowCreated<'a> {
+    #[serde(flatten)]
+    pub escrow: &'a Escrow,
+}
+impl_emit!(EscrowCreated);
+
+/// Data to log when the escrowed tokens are paid to the payee or returned to the payer.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EscrowSettled<'a> {
+    pub escrow_id: U64,
+    pub outcome: &'a EscrowOutcome,
+    pub settled_by: &'a AccountId,
+}
+impl_emit!(EscrowSettled);
@@ -0,0 +1,64 @@
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, require, AccountId};
+
+use crate::events::{AccountFrozen, AccountUnfrozen};
+use crate::{Contract, ContractExt};
+
+#[near_bindgen]
+impl Contract {
+    /// Freezes the balance of `account_id`, so the tokens cannot be transferred, burned, or moved
+    /// by the reward operators until the account is unfrozen. The contract account cannot be
+    /// frozen. Can only be called by the owner.
+    pub fn freeze_account(&mut self, account_id: AccountId, reason: String) {
+        self.assert_owner();
+        self.assert_not_contract_account(&account_id);
+        require!(
+            self.token
+                .frozen_accounts
+                .insert(&account_id, &reason)
+                .is_none(),
+            "The account is already frozen"
+        );
+        AccountFrozen {
+            account_id: &account_id,
+            reason: &reason,
+        }
+        .emit();
+    }
+
+    /// Unfreezes the balance of `account_id`. Can only be called by the owner.
+    pub fn unfreeze_account(&mut self, account_id: AccountId, reason: String) {
+        self.assert_owner();
+        require!(
+            self.token.frozen_accounts.remove(&account_id).is_some(),
+            "The account is not frozen"
+        );
+        AccountUnfrozen {
+            account_id: &account_id,
+            reason: &reason,
+        }
+        .emit();
+    }
+
+    /// Returns the reason why the account is frozen, or `None` if it is not frozen.
+    pub fn frozen_account_reason(&self, account_id: AccountId) -> Option<String> {
+        self.token.frozen_accounts.get(&account_id)
+    }
+
+    /// Returns the frozen accounts along with the reasons, starting from `from_index` (0 by
+    /// default) and returning at most `limit` entries (all by default).
+    pub fn frozen_accounts(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<(AccountId, String)> {
+        require!(limit != Some(0), "Cannot provide limit of 0.");
+        let start = usize::try_from(from_index.map_or(0, |index| index.0)).unwrap_or(usize::MAX);
+        self.token
+            .frozen_accounts
+            .iter()
+            .skip(start)
+            .take(usize::try_from(limit.unwrap_or(u64::MAX)).unwrap_or(usize::MAX))
+            .collect()
+    }
+}
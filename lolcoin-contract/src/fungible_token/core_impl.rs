@@ -1,9 +1,12 @@
 use crate::fungible_token::core::FungibleTokenCore;
+use crate::fungible_token::history::{AccountHistory, MovementKind};
 use crate::fungible_token::receiver::ext_ft_receiver;
 use crate::fungible_token::resolver::{ext_ft_resolver, FungibleTokenResolver};
-use near_contract_standards::fungible_token::events::{FtBurn, FtTransfer};
+use crate::fungible_token::seasons::{Season, SeasonId};
+use crate::fungible_token::snapshots::{AccountSnapshot, Snapshot, SnapshotId};
+use near_contract_standards::fungible_token::events::{FtBurn, FtMint, FtTransfer};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::UnorderedMap;
+use near_sdk::collections::{LookupMap, UnorderedMap, Vector};
 use near_sdk::json_types::U128;
 use near_sdk::{
     assert_one_yocto, env, log, require, AccountId, Balance, Gas, IntoStorageKey, PromiseOrValue,
@@ -34,28 +37,80 @@ pub struct FungibleToken {
 
     /// The storage size in bytes for one account.
     pub account_storage_usage: StorageUsage,
+
+    /// AccountID -> The reason why the account balance is frozen.
+    pub frozen_accounts: UnorderedMap<AccountId, String>,
+
+    /// All the seasons, the last one is the current season.
+    pub seasons: Vector<Season>,
+
+    /// AccountID -> The season of the account balance. Missing entries mean the first season.
+    pub account_seasons: LookupMap<AccountId, SeasonId>,
+
+    /// AccountID -> The most recent movements of the account balance.
+    pub account_history: LookupMap<AccountId, AccountHistory>,
+
+    /// All the snapshots, the last one is the latest snapshot.
+    pub snapshots: Vector<Snapshot>,
+
+    /// (AccountID, Snapshot ID) -> The account balance saved for the snapshot taken before it
+    /// changed.
+    pub account_snapshots: LookupMap<(AccountId, SnapshotId), AccountSnapshot>,
+
+    /// AccountID -> The latest snapshot the account balance was saved for.
+    pub account_latest_snapshot_ids: LookupMap<AccountId, SnapshotId>,
 }
 
 impl FungibleToken {
-    pub fn new<S>(prefix: S) -> Self
+    #[allow(clippy::too_many_arguments)]
+    pub fn new<S, F, T, A, H, N, B, L>(
+        prefix: S,
+        frozen_accounts_prefix: F,
+        seasons_prefix: T,
+        account_seasons_prefix: A,
+        account_history_prefix: H,
+        snapshots_prefix: N,
+        account_snapshots_prefix: B,
+        account_latest_snapshot_ids_prefix: L,
+    ) -> Self
     where
         S: IntoStorageKey,
+        F: IntoStorageKey,
+        T: IntoStorageKey,
+        A: IntoStorageKey,
+        H: IntoStorageKey,
+        N: IntoStorageKey,
+        B: IntoStorageKey,
+        L: IntoStorageKey,
     {
         let mut this = Self {
             accounts: UnorderedMap::new(prefix),
             total_supply: 0,
             account_storage_usage: 0,
+            frozen_accounts: UnorderedMap::new(frozen_accounts_prefix),
+            seasons: Vector::new(seasons_prefix),
+            account_seasons: LookupMap::new(account_seasons_prefix),
+            account_history: LookupMap::new(account_history_prefix),
+            snapshots: Vector::new(snapshots_prefix),
+            account_snapshots: LookupMap::new(account_snapshots_prefix),
+            account_latest_snapshot_ids: LookupMap::new(account_latest_snapshot_ids_prefix),
         };
         this.measure_account_storage_usage();
+        this.internal_open_season();
         this
     }
 
-    fn measure_account_storage_usage(&mut self) {
+    pub(crate) fn measure_account_storage_usage(&mut self) {
         let initial_storage_usage = env::storage_usage();
         let tmp_account_id = AccountId::new_unchecked("a".repeat(64));
         self.accounts.insert(&tmp_account_id, &0u128);
+        self.account_seasons.insert(&tmp_account_id, &0);
+        self.account_history
+            .insert(&tmp_account_id, &AccountHistory::largest(&tmp_account_id));
         self.account_storage_usage = env::storage_usage() - initial_storage_usage;
         self.accounts.remove(&tmp_account_id);
+        self.account_seasons.remove(&tmp_account_id);
+        self.account_history.remove(&tmp_account_id);
     }
 
     pub fn internal_unwrap_balance_of(&self, account_id: &AccountId) -> Balance {
@@ -68,6 +123,7 @@ impl FungibleToken {
     }
 
     pub fn internal_deposit(&mut self, account_id: &AccountId, amount: Balance) {
+        self.internal_settle_season(account_id);
         let balance = self.internal_unwrap_balance_of(account_id);
         if let Some(new_balance) = balance.checked_add(amount) {
             self.accounts.insert(account_id, &new_balance);
@@ -80,7 +136,20 @@ impl FungibleToken {
         }
     }
 
+    pub fn internal_assert_not_frozen(&self, account_id: &AccountId) {
+        if self.frozen_accounts.get(account_id).is_some() {
+            env::panic_str(format!("The account {} is frozen", &account_id).as_str())
+        }
+    }
+
     pub fn internal_withdraw(&mut self, account_id: &AccountId, amount: Balance) {
+        self.internal_assert_not_frozen(account_id);
+        self.internal_force_withdraw(account_id, amount);
+    }
+
+    /// Withdraws the tokens even if the account is frozen, e.g. to claw back erroneous rewards.
+    pub fn internal_force_withdraw(&mut self, account_id: &AccountId, amount: Balance) {
+        self.internal_settle_season(account_id);
         let balance = self.internal_unwrap_balance_of(account_id);
         if let Some(new_balance) = balance.checked_sub(amount) {
             self.accounts.insert(account_id, &new_balance);
@@ -99,6 +168,29 @@ impl FungibleToken {
         receiver_id: &AccountId,
         amount: Balance,
         memo: Option<String>,
+    ) {
+        self.internal_transfer_recorded_as(sender_id, receiver_id, amount, memo, false);
+    }
+
+    /// Same as `internal_transfer`, but records the movements as custodial transfers made by a
+    /// reward operator on behalf of the sender.
+    pub fn internal_custodial_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        self.internal_transfer_recorded_as(sender_id, receiver_id, amount, memo, true);
+    }
+
+    fn internal_transfer_recorded_as(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+        custodial: bool,
     ) {
         require!(
             sender_id != receiver_id,
@@ -107,6 +199,7 @@ impl FungibleToken {
         require!(amount > 0, "The amount should be a positive number");
         self.internal_withdraw(sender_id, amount);
         self.internal_deposit(receiver_id, amount);
+        self.internal_record_transfer(sender_id, receiver_id, amount, memo.as_deref(), custodial);
         FtTransfer {
             old_owner_id: sender_id,
             new_owner_id: receiver_id,
@@ -116,7 +209,85 @@ impl FungibleToken {
         .emit();
     }
 
+    /// Transfers the tokens to multiple receivers at once, checking the sender balance for the
+    /// total amount and logging a single `FtTransfer` event with an entry per receiver.
+    pub fn internal_transfer_batch(
+        &mut self,
+        sender_id: &AccountId,
+        transfers: &[(AccountId, U128, Option<String>)],
+    ) {
+        require!(!transfers.is_empty(), "The batch should not be empty");
+        let mut total_amount: Balance = 0;
+        for (receiver_id, amount, _) in transfers {
+            require!(
+                sender_id != receiver_id,
+                "Sender and receiver should be different"
+            );
+            require!(amount.0 > 0, "The amount should be a positive number");
+            total_amount = total_amount
+                .checked_add(amount.0)
+                .unwrap_or_else(|| env::panic_str("Transfer amount overflow"));
+        }
+        self.internal_withdraw(sender_id, total_amount);
+        let mut events = Vec::with_capacity(transfers.len());
+        for (receiver_id, amount, memo) in transfers {
+            self.internal_deposit(receiver_id, amount.0);
+            self.internal_record_transfer(sender_id, receiver_id, amount.0, memo.as_deref(), false);
+            events.push(FtTransfer {
+                old_owner_id: sender_id,
+                new_owner_id: receiver_id,
+                amount,
+                memo: memo.as_deref(),
+            });
+        }
+        FtTransfer::emit_many(&events);
+    }
+
+    pub fn internal_mint(&mut self, account_id: &AccountId, amount: Balance, memo: Option<&str>) {
+        self.internal_deposit(account_id, amount);
+        self.internal_record_movement(account_id, MovementKind::Mint, amount, None, memo);
+        FtMint {
+            owner_id: account_id,
+            amount: &U128(amount),
+            memo,
+        }
+        .emit();
+    }
+
+    pub fn internal_burn(&mut self, account_id: &AccountId, amount: Balance, memo: Option<&str>) {
+        require!(amount > 0, "The amount should be a positive number");
+        self.internal_withdraw(account_id, amount);
+        self.internal_record_movement(account_id, MovementKind::Burn, amount, None, memo);
+        FtBurn {
+            owner_id: account_id,
+            amount: &U128(amount),
+            memo,
+        }
+        .emit();
+    }
+
+    fn internal_record_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<&str>,
+        custodial: bool,
+    ) {
+        let (sender_kind, receiver_kind) = if custodial {
+            (
+                MovementKind::CustodialTransferOut,
+                MovementKind::CustodialTransferIn,
+            )
+        } else {
+            (MovementKind::TransferOut, MovementKind::TransferIn)
+        };
+        self.internal_record_movement(sender_id, sender_kind, amount, Some(receiver_id), memo);
+        self.internal_record_movement(receiver_id, receiver_kind, amount, Some(sender_id), memo);
+    }
+
     pub fn internal_register_account(&mut self, account_id: &AccountId) {
+        self.internal_record_registration_snapshot_balance(account_id);
         if self.accounts.insert(account_id, &0).is_some() {
             env::panic_str("The account is already registered");
         }
@@ -164,11 +335,16 @@ impl FungibleTokenCore for FungibleToken {
     }
 
     fn ft_total_supply(&self) -> U128 {
-        self.total_supply.into()
+        self.internal_season_converted_total_supply().into()
     }
 
     fn ft_balance_of(&self, account_id: AccountId) -> U128 {
-        self.accounts.get(&account_id).unwrap_or(0).into()
+        self.accounts
+            .get(&account_id)
+            .map_or(0, |balance| {
+                self.internal_season_balance_of(&account_id, balance)
+            })
+            .into()
     }
 }
 
@@ -197,6 +373,8 @@ impl FungibleToken {
         };
 
         if unused_amount > 0 {
+            self.internal_settle_season(&receiver_id);
+            self.internal_settle_season(sender_id);
             let receiver_balance = self.accounts.get(&receiver_id).unwrap_or(0);
             if receiver_balance > 0 {
                 let refund_amount = std::cmp::min(receiver_balance, unused_amount);
@@ -212,6 +390,13 @@ impl FungibleToken {
                     } else {
                         env::panic_str("Sender balance overflow");
                     }
+                    self.internal_record_transfer(
+                        &receiver_id,
+                        sender_id,
+                        refund_amount,
+                        Some("refund"),
+                        false,
+                    );
 
                     FtTransfer {
                         old_owner_id: &receiver_id,
@@ -231,6 +416,13 @@ impl FungibleToken {
                         .checked_sub(refund_amount)
                         .unwrap_or_else(|| env::panic_str(ERR_TOTAL_SUPPLY_OVERFLOW));
                     log!("The account of the sender was deleted");
+                    self.internal_record_movement(
+                        &receiver_id,
+                        MovementKind::Burn,
+                        refund_amount,
+                        None,
+                        Some("refund"),
+                    );
                     FtBurn {
                         owner_id: &receiver_id,
                         amount: &U128(refund_amount),
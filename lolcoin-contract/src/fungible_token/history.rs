@@ -0,0 +1,120 @@
+use crate::fungible_token::FungibleToken;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId, Balance};
+
+/// The number of the most recent movements kept per account.
+pub const MAX_HISTORY_LENGTH: u64 = 10;
+
+/// The longer memos are truncated in the history to bound its storage.
+const MAX_HISTORY_MEMO_BYTES: usize = 32;
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum MovementKind {
+    Mint,
+    Burn,
+    TransferIn,
+    TransferOut,
+    /// A transfer made by a reward operator on behalf of the sender.
+    CustodialTransferIn,
+    CustodialTransferOut,
+}
+
+/// A change of the account balance, mirroring the logged NEP-141 event.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Movement {
+    pub kind: MovementKind,
+    pub amount: U128,
+    /// The other side of a transfer.
+    pub counterparty_id: Option<AccountId>,
+    pub memo: Option<String>,
+    pub block_height: U64,
+}
+
+/// A ring buffer of the most recent movements of an account.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct AccountHistory {
+    /// The movement with index `i` is stored at `i % MAX_HISTORY_LENGTH`.
+    movements: Vec<Movement>,
+    /// The number of the movements recorded since the account registration.
+    movements_count: u64,
+}
+
+impl AccountHistory {
+    /// Returns the history taking up the most storage, to include it in the storage paid for on
+    /// the account registration.
+    pub(crate) fn largest(account_id: &AccountId) -> Self {
+        let movement = Movement {
+            kind: MovementKind::CustodialTransferOut,
+            amount: u128::MAX.into(),
+            counterparty_id: Some(account_id.clone()),
+            memo: Some("a".repeat(MAX_HISTORY_MEMO_BYTES)),
+            block_height: u64::MAX.into(),
+        };
+        Self {
+            movements: vec![movement; MAX_HISTORY_LENGTH as usize],
+            movements_count: u64::MAX,
+        }
+    }
+}
+
+impl FungibleToken {
+    pub fn internal_record_movement(
+        &mut self,
+        account_id: &AccountId,
+        kind: MovementKind,
+        amount: Balance,
+        counterparty_id: Option<&AccountId>,
+        memo: Option<&str>,
+    ) {
+        let memo = memo.map(|memo| {
+            let mut end = memo.len().min(MAX_HISTORY_MEMO_BYTES);
+            while !memo.is_char_boundary(end) {
+                end -= 1;
+            }
+            memo[..end].to_string()
+        });
+        let movement = Movement {
+            kind,
+            amount: amount.into(),
+            counterparty_id: counterparty_id.cloned(),
+            memo,
+            block_height: env::block_height().into(),
+        };
+        let mut history = self.account_history.get(account_id).unwrap_or_default();
+        let slot = (history.movements_count % MAX_HISTORY_LENGTH) as usize;
+        if slot < history.movements.len() {
+            history.movements[slot] = movement;
+        } else {
+            history.movements.push(movement);
+        }
+        history.movements_count += 1;
+        self.account_history.insert(account_id, &history);
+    }
+
+    /// Returns the recorded movements of the account along with their indexes, oldest first,
+    /// starting from `from_index` (the oldest kept movement by default).
+    pub fn internal_history_of(
+        &self,
+        account_id: &AccountId,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<(U64, Movement)> {
+        let Some(history) = self.account_history.get(account_id) else {
+            return vec![];
+        };
+        let oldest_index = history.movements_count - history.movements.len() as u64;
+        let from_index = std::cmp::max(from_index.unwrap_or(0), oldest_index);
+        (from_index..history.movements_count)
+            .take(limit.unwrap_or(MAX_HISTORY_LENGTH) as usize)
+            .map(|index| {
+                let movement = &history.movements[(index % MAX_HISTORY_LENGTH) as usize];
+                (index.into(), movement.clone())
+            })
+            .collect()
+    }
+}
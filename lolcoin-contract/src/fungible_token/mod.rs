@@ -1,8 +1,11 @@
 pub mod core;
 pub mod core_impl;
+pub mod history;
 pub mod metadata;
 pub mod receiver;
 pub mod resolver;
+pub mod seasons;
+pub mod snapshots;
 pub mod storage_impl;
 
 pub use core_impl::FungibleToken;
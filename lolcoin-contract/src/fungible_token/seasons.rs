@@ -0,0 +1,195 @@
+use crate::fungible_token::history::MovementKind;
+use crate::fungible_token::FungibleToken;
+use crate::internal::mul_div;
+use near_contract_standards::fungible_token::events::{FtBurn, FtMint};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, require, AccountId, Balance};
+
+pub type SeasonId = u32;
+
+/// The conversion rate at which the balances carry over into the next season in full.
+pub const FULL_CONVERSION_RATE_BPS: u32 = 10_000;
+
+/// The tokens are minted into the current season. Once a season is closed, the balances left
+/// from it are converted into the next season at the conversion rate of the closed season (0 means
+/// the balances expire). The conversion is applied lazily, the next time the account balance is
+/// touched, but `ft_total_supply` reports the supply as if it was applied already.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Season {
+    pub started_at: U64,
+    pub closed_at: Option<U64>,
+    /// The rate (in basis points) at which the balances of the closed season are converted into
+    /// the next season: 0 burns them, 10000 carries them over in full.
+    pub conversion_rate_bps: Option<u32>,
+    /// The balances of the closed season that are not converted yet.
+    pub outstanding_supply: U128,
+}
+
+fn convert(balance: Balance, conversion_rate_bps: u32) -> Balance {
+    mul_div(
+        balance,
+        conversion_rate_bps.into(),
+        FULL_CONVERSION_RATE_BPS.into(),
+    )
+}
+
+impl FungibleToken {
+    pub fn current_season_id(&self) -> SeasonId {
+        // There is always at least one season, see `FungibleToken::new`.
+        (self.seasons.len() - 1) as SeasonId
+    }
+
+    pub fn internal_unwrap_season(&self, season_id: SeasonId) -> Season {
+        self.seasons
+            .get(u64::from(season_id))
+            .unwrap_or_else(|| env::panic_str("The season does not exist"))
+    }
+
+    /// Returns the season the balance of the account was minted in or converted into.
+    pub fn internal_account_season_id(&self, account_id: &AccountId) -> SeasonId {
+        self.account_seasons.get(account_id).unwrap_or(0)
+    }
+
+    /// Returns the balance converted from the `season_id` into the current season.
+    pub fn internal_season_converted_balance(
+        &self,
+        balance: Balance,
+        season_id: SeasonId,
+    ) -> Balance {
+        self.internal_season_converted_balance_into(balance, season_id, self.current_season_id())
+    }
+
+    /// Returns the balance converted from the `from_season_id` into the `to_season_id`.
+    pub fn internal_season_converted_balance_into(
+        &self,
+        mut balance: Balance,
+        from_season_id: SeasonId,
+        to_season_id: SeasonId,
+    ) -> Balance {
+        for season_id in from_season_id..to_season_id {
+            let season = self.internal_unwrap_season(season_id);
+            balance = convert(balance, season.conversion_rate_bps.unwrap());
+        }
+        balance
+    }
+
+    /// Returns the balance of the account in the current season, i.e. with the balance from the
+    /// closed seasons converted.
+    pub fn internal_season_balance_of(&self, account_id: &AccountId, balance: Balance) -> Balance {
+        self.internal_season_converted_balance(balance, self.internal_account_season_id(account_id))
+    }
+
+    /// Converts the balance of the account left from the closed seasons into the current season,
+    /// burning or minting the difference.
+    ///
+    /// Every balance change settles the season first, so this is also where the balance is saved
+    /// for the latest snapshot.
+    pub fn internal_settle_season(&mut self, account_id: &AccountId) {
+        self.internal_record_snapshot_balance(account_id);
+        let current_season_id = self.current_season_id();
+        let account_season_id = self.internal_account_season_id(account_id);
+        if account_season_id == current_season_id {
+            return;
+        }
+        let Some(balance) = self.accounts.get(account_id) else {
+            return;
+        };
+        self.account_seasons.insert(account_id, &current_season_id);
+        if balance == 0 {
+            return;
+        }
+
+        let mut season = self.internal_unwrap_season(account_season_id);
+        season.outstanding_supply = (season.outstanding_supply.0 - balance).into();
+        self.seasons.replace(u64::from(account_season_id), &season);
+
+        let new_balance = self.internal_season_converted_balance(balance, account_season_id);
+        self.accounts.insert(account_id, &new_balance);
+        self.total_supply = self.total_supply - balance + new_balance;
+        let memo = format!("season {} conversion", account_season_id);
+        if new_balance < balance {
+            self.internal_record_movement(
+                account_id,
+                MovementKind::Burn,
+                balance - new_balance,
+                None,
+                Some(&memo),
+            );
+            FtBurn {
+                owner_id: account_id,
+                amount: &U128(balance - new_balance),
+                memo: Some(&memo),
+            }
+            .emit();
+        } else if new_balance > balance {
+            self.internal_record_movement(
+                account_id,
+                MovementKind::Mint,
+                new_balance - balance,
+                None,
+                Some(&memo),
+            );
+            FtMint {
+                owner_id: account_id,
+                amount: &U128(new_balance - balance),
+                memo: Some(&memo),
+            }
+            .emit();
+        }
+    }
+
+    /// Returns the supply held in the current season, i.e. the total supply without the balances
+    /// left from the closed seasons.
+    pub fn internal_current_season_supply(&self) -> Balance {
+        let outstanding_supply_of_closed_seasons: Balance = self
+            .seasons
+            .iter()
+            .map(|season| season.outstanding_supply.0)
+            .sum();
+        self.total_supply - outstanding_supply_of_closed_seasons
+    }
+
+    /// Returns the total supply with the balances left from the closed seasons converted into the
+    /// current season, as if all the accounts were settled. Settling the accounts may burn up to a
+    /// few more tokens, since every balance is converted rounding down.
+    pub fn internal_season_converted_total_supply(&self) -> Balance {
+        let current_season_id = self.current_season_id();
+        let converted_supply_of_closed_seasons: Balance = (0..current_season_id)
+            .map(|season_id| {
+                let season = self.internal_unwrap_season(season_id);
+                self.internal_season_converted_balance(season.outstanding_supply.0, season_id)
+            })
+            .sum();
+        self.internal_current_season_supply() + converted_supply_of_closed_seasons
+    }
+
+    /// Closes the current season with the given conversion rate and opens the next one.
+    ///
+    /// Returns the id of the opened season.
+    pub fn internal_close_season(&mut self, conversion_rate_bps: u32) -> SeasonId {
+        require!(
+            conversion_rate_bps <= FULL_CONVERSION_RATE_BPS,
+            "The conversion rate cannot exceed 10000 basis points"
+        );
+        let closed_season_id = self.current_season_id();
+        let mut season = self.internal_unwrap_season(closed_season_id);
+        season.closed_at = Some(env::block_timestamp().into());
+        season.conversion_rate_bps = Some(conversion_rate_bps);
+        season.outstanding_supply = self.internal_current_season_supply().into();
+        self.seasons.replace(u64::from(closed_season_id), &season);
+        self.internal_open_season()
+    }
+
+    pub(crate) fn internal_open_season(&mut self) -> SeasonId {
+        self.seasons.push(&Season {
+            started_at: env::block_timestamp().into(),
+            closed_at: None,
+            conversion_rate_bps: None,
+            outstanding_supply: 0.into(),
+        });
+        self.current_season_id()
+    }
+}
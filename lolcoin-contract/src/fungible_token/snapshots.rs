@@ -0,0 +1,120 @@
+use crate::fungible_token::seasons::SeasonId;
+use crate::fungible_token::FungibleToken;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId, Balance};
+
+pub type SnapshotId = u32;
+
+/// A point in time the balances can be queried at. The balances are recorded lazily: the balance
+/// of an account is saved before it changes for the first time after the snapshot.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Snapshot {
+    pub taken_at: U64,
+    /// The total supply converted into `season_id`, as reported by `ft_total_supply`.
+    pub total_supply: U128,
+    /// The season that was current when the snapshot was taken.
+    pub season_id: SeasonId,
+}
+
+/// The account balance as of the snapshot.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AccountSnapshot {
+    balance: Balance,
+    season_id: SeasonId,
+    /// The previous snapshot the balance of the account was saved for.
+    previous_snapshot_id: Option<SnapshotId>,
+}
+
+impl FungibleToken {
+    pub fn internal_unwrap_snapshot(&self, snapshot_id: SnapshotId) -> Snapshot {
+        self.snapshots
+            .get(u64::from(snapshot_id))
+            .unwrap_or_else(|| env::panic_str("The snapshot does not exist"))
+    }
+
+    /// Returns the id of the latest snapshot, if any.
+    pub fn current_snapshot_id(&self) -> Option<SnapshotId> {
+        self.snapshots
+            .len()
+            .checked_sub(1)
+            .map(|snapshot_id| snapshot_id as SnapshotId)
+    }
+
+    pub fn internal_take_snapshot(&mut self) -> SnapshotId {
+        self.snapshots.push(&Snapshot {
+            taken_at: env::block_timestamp().into(),
+            total_supply: self.internal_season_converted_total_supply().into(),
+            season_id: self.current_season_id(),
+        });
+        self.current_snapshot_id().unwrap()
+    }
+
+    /// Saves the balance of the registered account for the latest snapshot unless it was saved
+    /// already. Must be called before the balance changes.
+    ///
+    /// Every registered account stores at most one balance per snapshot, paid for by the contract
+    /// rather than by the registration deposit, so the snapshots should be taken sparingly.
+    pub(crate) fn internal_record_snapshot_balance(&mut self, account_id: &AccountId) {
+        if let Some(balance) = self.accounts.get(account_id) {
+            self.internal_save_snapshot_balance(account_id, balance);
+        }
+    }
+
+    /// Saves the zero balance of the account being registered, so that it does not count for
+    /// the snapshots taken before the registration.
+    pub(crate) fn internal_record_registration_snapshot_balance(&mut self, account_id: &AccountId) {
+        self.internal_save_snapshot_balance(account_id, 0);
+    }
+
+    fn internal_save_snapshot_balance(&mut self, account_id: &AccountId, balance: Balance) {
+        let Some(snapshot_id) = self.current_snapshot_id() else {
+            return;
+        };
+        let latest_snapshot_id = self.account_latest_snapshot_ids.get(account_id);
+        if latest_snapshot_id == Some(snapshot_id) {
+            return;
+        }
+        self.account_snapshots.insert(
+            &(account_id.clone(), snapshot_id),
+            &AccountSnapshot {
+                balance,
+                season_id: self.internal_account_season_id(account_id),
+                previous_snapshot_id: latest_snapshot_id,
+            },
+        );
+        self.account_latest_snapshot_ids
+            .insert(account_id, &snapshot_id);
+    }
+
+    /// Returns the balance of the account at the snapshot, converted into the season that was
+    /// current back then.
+    ///
+    /// The lookup walks over the balances saved for the snapshots taken since the requested one,
+    /// so it is the cheapest for the recent snapshots.
+    pub fn internal_balance_of_at(
+        &self,
+        account_id: &AccountId,
+        snapshot_id: SnapshotId,
+    ) -> Balance {
+        let snapshot = self.internal_unwrap_snapshot(snapshot_id);
+        // The first balance saved at or after the snapshot is the balance at the snapshot, so walk
+        // back from the latest saved one. If the balance has not changed since the snapshot, it is
+        // the current one.
+        let mut balance = self.accounts.get(account_id).unwrap_or(0);
+        let mut season_id = self.internal_account_season_id(account_id);
+        let mut saved_snapshot_id = self.account_latest_snapshot_ids.get(account_id);
+        while let Some(id) = saved_snapshot_id.filter(|id| *id >= snapshot_id) {
+            let account_snapshot = self
+                .account_snapshots
+                .get(&(account_id.clone(), id))
+                .unwrap();
+            balance = account_snapshot.balance;
+            season_id = account_snapshot.season_id;
+            saved_snapshot_id = account_snapshot.previous_snapshot_id;
+        }
+        self.internal_season_converted_balance_into(balance, season_id, snapshot.season_id)
+    }
+}
@@ -7,7 +7,7 @@ use near_sdk::{assert_one_yocto, env, log, AccountId, Balance, Promise};
 
 impl FungibleToken {
     /// Internal method that returns the Account ID and the balance in case the account was
-    /// unregistered.
+    /// unregistered. The caller is responsible for refunding the storage deposit.
     pub fn internal_storage_unregister(
         &mut self,
         force: Option<bool>,
@@ -15,11 +15,14 @@ impl FungibleToken {
         assert_one_yocto();
         let account_id = env::predecessor_account_id();
         let force = force.unwrap_or(false);
+        self.internal_settle_season(&account_id);
         if let Some(balance) = self.accounts.get(&account_id) {
+            self.internal_assert_not_frozen(&account_id);
             if balance == 0 || force {
                 self.accounts.remove(&account_id);
+                self.account_seasons.remove(&account_id);
+                self.account_history.remove(&account_id);
                 self.total_supply -= balance;
-                Promise::new(account_id.clone()).transfer(self.storage_balance_bounds().min.0 + 1);
                 Some((account_id, balance))
             } else {
                 env::panic_str(
@@ -98,7 +101,12 @@ impl StorageManagement for FungibleToken {
     }
 
     fn storage_unregister(&mut self, force: Option<bool>) -> bool {
-        self.internal_storage_unregister(force).is_some()
+        if let Some((account_id, _)) = self.internal_storage_unregister(force) {
+            Promise::new(account_id).transfer(self.storage_balance_bounds().min.0 + 1);
+            true
+        } else {
+            false
+        }
     }
 
     fn storage_balance_bounds(&self) -> StorageBalanceBounds {
@@ -0,0 +1,93 @@
+//! Custodial transfers authorized by the sender's signature.
+//!
+//! Student accounts are implicit accounts, i.e. the account id is the hex-encoded ed25519 public
+//! key the api-server derives from the seed phrase. The student signs a [`TransferIntent`] with that
+//! key and the custodian relays it with `signed_custodial_ft_transfer`, so the custodian cannot
+//! spend the student tokens without the student consent.
+use ed25519_dalek::Verifier;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::pause::PausableFeature;
+use crate::{Contract, ContractExt};
+
+/// The transfer the sender consents to. The signed message is the borsh serialization of the
+/// intent.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferIntent {
+    /// The account of this contract, so the intent cannot be replayed on other deployments.
+    pub contract_id: AccountId,
+    pub sender_id: AccountId,
+    pub receiver_id: AccountId,
+    pub amount: U128,
+    pub memo: Option<String>,
+    /// Must be greater than the nonce of the last intent of the sender (see `ft_intent_nonce`).
+    pub nonce: U64,
+    /// The block timestamp (in nanoseconds) after which the intent cannot be executed.
+    pub expires_at: U64,
+}
+
+/// Returns the ed25519 public key of the implicit account.
+fn implicit_account_public_key(account_id: &AccountId) -> Option<ed25519_dalek::PublicKey> {
+    let account_id = account_id.as_str();
+    if account_id.len() != 64 {
+        return None;
+    }
+    let mut public_key = [0u8; 32];
+    for (byte, hex) in public_key.iter_mut().zip(account_id.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?;
+    }
+    ed25519_dalek::PublicKey::from_bytes(&public_key).ok()
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Transfers tokens as described by the `intent` signed (ed25519) by the key of the sender
+    /// implicit account. Can only be called by reward operators.
+    pub fn signed_custodial_ft_transfer(&mut self, intent: TransferIntent, signature: Base64VecU8) {
+        self.assert_reward_operator();
+        self.assert_not_paused(PausableFeature::CustodialTransfers);
+        self.assert_not_contract_account(&intent.sender_id);
+        require!(
+            intent.contract_id == env::current_account_id(),
+            "The intent is issued for another contract"
+        );
+        require!(
+            env::block_timestamp() <= intent.expires_at.0,
+            "The intent has expired"
+        );
+        require!(
+            intent.nonce.0 > self.ft_intent_nonce(intent.sender_id.clone()).0,
+            "The intent nonce has already been used"
+        );
+
+        let public_key = implicit_account_public_key(&intent.sender_id).unwrap_or_else(|| {
+            env::panic_str("Signed intents are only supported for implicit accounts")
+        });
+        let signature = ed25519_dalek::Signature::from_bytes(&signature.0)
+            .unwrap_or_else(|_| env::panic_str("Invalid signature"));
+        let message = intent.try_to_vec().unwrap();
+        require!(
+            public_key.verify(&message, &signature).is_ok(),
+            "Invalid signature"
+        );
+
+        self.intent_nonces
+            .insert(&intent.sender_id, &intent.nonce.0);
+        self.internal_claim_vested_before_spending(&intent.sender_id);
+        self.token.internal_custodial_transfer(
+            &intent.sender_id,
+            &intent.receiver_id,
+            intent.amount.into(),
+            intent.memo,
+        );
+    }
+
+    /// Returns the nonce of the last executed intent signed by the account (0 if none).
+    pub fn ft_intent_nonce(&self, account_id: AccountId) -> U64 {
+        self.intent_nonces.get(&account_id).unwrap_or(0).into()
+    }
+}
@@ -1,6 +1,54 @@
-use near_sdk::env;
+use near_sdk::collections::LookupMap;
+use near_sdk::{env, require, AccountId, Balance, Promise, StorageUsage};
+
+use crate::events::FtMetadataUpdated;
+use crate::fungible_token::history::MovementKind;
+use crate::fungible_token::metadata::FungibleTokenMetadata;
+use crate::fungible_token::seasons::SeasonId;
+use crate::Reward;
+
+/// Returns `amount * numerator / denominator` rounded down.
+pub(crate) fn mul_div(amount: Balance, numerator: u128, denominator: u128) -> Balance {
+    // Split the multiplication to avoid overflowing on large amounts.
+    amount / denominator * numerator + amount % denominator * numerator / denominator
+}
+
+/// Adds the id to the ids of the account in the index, e.g. the streams the account receives.
+pub(crate) fn insert_into_index(
+    index: &mut LookupMap<AccountId, Vec<u64>>,
+    account_id: &AccountId,
+    id: u64,
+) {
+    let mut ids = index.get(account_id).unwrap_or_default();
+    ids.push(id);
+    index.insert(account_id, &ids);
+}
+
+/// Removes the id from the ids of the account in the index, dropping the entry once it is empty.
+pub(crate) fn remove_from_index(
+    index: &mut LookupMap<AccountId, Vec<u64>>,
+    account_id: &AccountId,
+    id: u64,
+) {
+    let mut ids = index.get(account_id).unwrap_or_default();
+    ids.retain(|existing_id| *existing_id != id);
+    if ids.is_empty() {
+        index.remove(account_id);
+    } else {
+        index.insert(account_id, &ids);
+    }
+}
 
 impl crate::Contract {
+    /// Asserts that the method was called by the contract owner
+    pub(crate) fn assert_owner(&self) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner_id,
+            "Can only be called by the owner"
+        );
+    }
+
     /// Asserts that the method was called by a reward operator
     pub(crate) fn assert_reward_operator(&self) {
         assert!(
@@ -9,4 +57,194 @@ impl crate::Contract {
             "Can only be called by reward operators"
         );
     }
+
+    /// Asserts that the account is not the contract account, whose balance holds the tokens
+    /// locked in the stakes, streams and escrows
+    pub(crate) fn assert_not_contract_account(&self, account_id: &AccountId) {
+        require!(
+            *account_id != env::current_account_id(),
+            "Cannot be applied to the contract account"
+        );
+    }
+
+    /// Asserts that the account has no tokens locked in vesting grants, stakes, streams or
+    /// escrows, which could not be paid out once the account is unregistered
+    pub(crate) fn assert_can_unregister(&self, account_id: &AccountId) {
+        require!(
+            self.vesting_grants.get(account_id).is_none(),
+            "The account has unclaimed vesting grants"
+        );
+        require!(
+            self.stakes.get(account_id).is_none(),
+            "The account has active stakes"
+        );
+        require!(
+            self.account_streams.get(account_id).is_none()
+                && self.received_streams.get(account_id).is_none(),
+            "The account has active streams"
+        );
+        require!(
+            self.account_escrows.get(account_id).is_none()
+                && self.received_escrows.get(account_id).is_none(),
+            "The account has active escrows"
+        );
+    }
+
+    /// Registers the account with the token unless it is already registered, sponsoring its
+    /// storage on behalf of the calling reward operator
+    pub(crate) fn internal_register_account_if_needed(&mut self, account_id: &AccountId) {
+        if let Err(error) =
+            self.internal_try_register_account_if_needed(account_id, &env::predecessor_account_id())
+        {
+            env::panic_str(error);
+        }
+    }
+
+    /// Same as `internal_register_account_if_needed`, but sponsors the storage on behalf of the
+    /// given reward operator and returns the error instead of panicking
+    pub(crate) fn internal_try_register_account_if_needed(
+        &mut self,
+        account_id: &AccountId,
+        operator_id: &AccountId,
+    ) -> Result<(), &'static str> {
+        if self.token.accounts.get(account_id).is_none() {
+            let sponsor = self.internal_try_take_operator_sponsorship(operator_id)?;
+            self.token.internal_register_account(account_id);
+            self.internal_record_storage_sponsor(account_id, sponsor);
+        }
+        Ok(())
+    }
+
+    /// Mints the rewards on behalf of the reward operator, charging the operator quota and
+    /// sponsoring the storage of the new accounts out of the operator's deposit.
+    pub(crate) fn internal_reward(
+        &mut self,
+        operator_id: &AccountId,
+        rewards: &[Reward],
+        memo: Option<&str>,
+    ) {
+        let amounts: Vec<Balance> = rewards
+            .iter()
+            .map(|reward| reward.tokens_amount.0)
+            .collect();
+        self.internal_charge_reward_quota(operator_id, &amounts);
+        let mut events = vec![];
+        for reward in rewards {
+            let reward_memo = reward.memo.as_deref().or(memo);
+            if let Err(error) =
+                self.internal_try_register_account_if_needed(&reward.target_account_id, operator_id)
+            {
+                env::panic_str(error);
+            }
+            self.token
+                .internal_deposit(&reward.target_account_id, reward.tokens_amount.into());
+            self.token.internal_record_movement(
+                &reward.target_account_id,
+                MovementKind::Mint,
+                reward.tokens_amount.into(),
+                None,
+                reward_memo,
+            );
+            events.push(near_contract_standards::fungible_token::events::FtMint {
+                owner_id: &reward.target_account_id,
+                amount: &reward.tokens_amount,
+                memo: reward_memo,
+            });
+        }
+        near_contract_standards::fungible_token::events::FtMint::emit_many(&events);
+    }
+
+    /// Moves the tokens locked in a stake, stream or escrow from the account to the contract
+    /// account, registering the contract account on first use.
+    pub(crate) fn internal_lock_tokens(
+        &mut self,
+        account_id: &AccountId,
+        amount: Balance,
+        memo: &str,
+    ) {
+        let contract_id = env::current_account_id();
+        if self.token.accounts.get(&contract_id).is_none() {
+            self.token.internal_register_account(&contract_id);
+        }
+        self.token
+            .internal_transfer(account_id, &contract_id, amount, Some(memo.to_string()));
+    }
+
+    /// Pays the tokens locked in the `season_id` out of the contract account, converted into the
+    /// current season like the rest of the contract account balance.
+    ///
+    /// Returns the paid amount.
+    pub(crate) fn internal_unlock_tokens(
+        &mut self,
+        account_id: &AccountId,
+        amount: Balance,
+        season_id: SeasonId,
+        memo: &str,
+    ) -> Balance {
+        let amount = self
+            .token
+            .internal_season_converted_balance(amount, season_id);
+        if amount > 0 {
+            self.token.internal_transfer(
+                &env::current_account_id(),
+                account_id,
+                amount,
+                Some(memo.to_string()),
+            );
+        }
+        amount
+    }
+
+    /// Charges the caller for the storage added since `initial_storage_usage` out of the attached
+    /// deposit and refunds the rest, along with the cost of the released storage, if any.
+    pub(crate) fn internal_charge_storage_deposit(&self, initial_storage_usage: StorageUsage) {
+        let storage_cost =
+            |storage_usage: StorageUsage| Balance::from(storage_usage) * env::storage_byte_cost();
+        let attached_deposit = env::attached_deposit();
+        let current_storage_usage = env::storage_usage();
+        let refund = if current_storage_usage >= initial_storage_usage {
+            let required_deposit = storage_cost(current_storage_usage - initial_storage_usage);
+            attached_deposit
+                .checked_sub(required_deposit)
+                .unwrap_or_else(|| {
+                    env::panic_str(&format!(
+                        "Must attach {} yoctoNEAR to cover storage",
+                        required_deposit
+                    ))
+                })
+        } else {
+            attached_deposit + storage_cost(initial_storage_usage - current_storage_usage)
+        };
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+    }
+
+    /// Refunds the cost of the storage released since `initial_storage_usage` to the account that
+    /// paid for it.
+    pub(crate) fn internal_refund_released_storage(
+        &self,
+        account_id: &AccountId,
+        initial_storage_usage: StorageUsage,
+    ) {
+        let released_storage = initial_storage_usage.saturating_sub(env::storage_usage());
+        if released_storage > 0 {
+            Promise::new(account_id.clone())
+                .transfer(Balance::from(released_storage) * env::storage_byte_cost());
+        }
+    }
+
+    /// Applies `update` to the token metadata, validates the result, and emits an event listing
+    /// the `updated_fields`.
+    pub(crate) fn internal_update_metadata(
+        &mut self,
+        updated_fields: &[&str],
+        update: impl FnOnce(&mut FungibleTokenMetadata),
+    ) {
+        let mut metadata = self.metadata.get().unwrap();
+        update(&mut metadata);
+        metadata.assert_valid();
+        self.metadata.set(&metadata);
+        FtMetadataUpdated { updated_fields }.emit();
+    }
 }
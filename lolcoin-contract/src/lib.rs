@@ -19,34 +19,143 @@ use near_contract_standards::storage_management::{
     StorageBalance, StorageBalanceBounds, StorageManagement,
 };
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LazyOption;
-use near_sdk::json_types::U128;
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedMap, Vector};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{log, near_bindgen, AccountId, PanicOnDefault, PromiseOrValue};
+use near_sdk::{
+    assert_one_yocto, env, log, near_bindgen, require, AccountId, Balance, BorshStorageKey,
+    PanicOnDefault, PromiseOrValue,
+};
 
+use crate::escrows::Escrow;
+use crate::events::AccountClosed;
 use crate::fungible_token::core::FungibleTokenCore;
+use crate::fungible_token::history::{Movement, MovementKind};
 use crate::fungible_token::metadata::{
     FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC,
 };
 use crate::fungible_token::resolver::FungibleTokenResolver;
 use crate::fungible_token::FungibleToken;
+use crate::operators::RewardOperatorQuota;
+use crate::pause::{PausableFeature, PauseFlags};
+use crate::polls::{Poll, PollVote};
+use crate::proposals::{MultisigPolicy, RewardProposal};
+use crate::schedules::RewardSchedule;
+use crate::sponsorship::StorageSponsor;
+use crate::staking::{Stake, StakingTerm};
+use crate::streams::Stream;
+use crate::vesting::VestingGrant;
 
 /// Contains a fork of fungible token standard implementation from near-sdk-rs to support iteration
 /// over accounts (use UnorderedMap instead of LookupMap).
 /// https://github.com/near/near-sdk-rs/tree/de975ed75e0f6a840c7aeb57e3414959cb59bc00/near-contract-standards/src/fungible_token
 mod fungible_token;
 
+mod allowances;
+mod escrows;
+mod events;
+mod freeze;
+mod intents;
 mod internal;
+mod migrate;
+mod operators;
+mod pause;
+mod polls;
+mod proposals;
+mod schedules;
+mod seasons;
+mod snapshots;
+mod sponsorship;
+mod staking;
+mod streams;
+mod vesting;
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
+    owner_id: AccountId,
     token: FungibleToken,
     metadata: LazyOption<FungibleTokenMetadata>,
     reward_operators: std::collections::HashSet<AccountId>,
+    reward_operator_quotas: LookupMap<AccountId, RewardOperatorQuota>,
+    pause_flags: PauseFlags,
+    applied_reward_batches: LookupSet<String>,
+    vesting_grants: LookupMap<AccountId, Vec<VestingGrant>>,
+    next_vesting_grant_id: u64,
+    /// (Owner, Spender) -> The amount of the owner tokens the spender can transfer.
+    allowances: LookupMap<(AccountId, AccountId), Balance>,
+    /// Owner -> The spenders the owner has granted allowances to.
+    allowance_spenders: LookupMap<AccountId, Vec<AccountId>>,
+    /// The nonces of the last executed signed transfer intents.
+    intent_nonces: LookupMap<AccountId, u64>,
+    multisig_policy: Option<MultisigPolicy>,
+    reward_proposals: UnorderedMap<u64, RewardProposal>,
+    next_reward_proposal_id: u64,
+    polls: Vector<Poll>,
+    /// (Poll ID, Voter) -> The vote.
+    poll_votes: LookupMap<(u64, AccountId), PollVote>,
+    /// AccountID -> Who paid for the storage of the account.
+    storage_sponsors: LookupMap<AccountId, StorageSponsor>,
+    /// Reward operator -> The deposit available to pay for the storage of new accounts.
+    operator_storage_deposits: LookupMap<AccountId, Balance>,
+    pool_sponsored_registrations: u64,
+    max_pool_sponsored_registrations: Option<u64>,
+    staking_terms: Vec<StakingTerm>,
+    stakes: LookupMap<AccountId, Vec<Stake>>,
+    next_stake_id: u64,
+    total_staked: Balance,
+    reward_schedules: UnorderedMap<u64, RewardSchedule>,
+    next_reward_schedule_id: u64,
+    /// The index of the reward schedule `process_due_payouts` continues from.
+    reward_payouts_cursor: u64,
+    streams: LookupMap<u64, Stream>,
+    /// AccountID -> The streams the account sends.
+    account_streams: LookupMap<AccountId, Vec<u64>>,
+    /// AccountID -> The streams the account receives.
+    received_streams: LookupMap<AccountId, Vec<u64>>,
+    next_stream_id: u64,
+    escrows: LookupMap<u64, Escrow>,
+    /// AccountID -> The escrows the account is the payer of.
+    account_escrows: LookupMap<AccountId, Vec<u64>>,
+    /// AccountID -> The escrows the account is the payee of.
+    received_escrows: LookupMap<AccountId, Vec<u64>>,
+    /// AccountID -> The escrows the account is the arbiter of.
+    arbitrated_escrows: LookupMap<AccountId, Vec<u64>>,
+    next_escrow_id: u64,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    RewardOperatorQuotas,
+    FrozenAccounts,
+    AppliedRewardBatches,
+    VestingGrants,
+    Seasons,
+    AccountSeasons,
+    Allowances,
+    IntentNonces,
+    AccountHistory,
+    RewardProposals,
+    Snapshots,
+    AccountSnapshots,
+    Polls,
+    PollVotes,
+    StorageSponsors,
+    OperatorStorageDeposits,
+    Stakes,
+    RewardSchedules,
+    Streams,
+    AccountStreams,
+    Escrows,
+    AccountEscrows,
+    AllowanceSpenders,
+    AccountLatestSnapshotIds,
+    ReceivedStreams,
+    ReceivedEscrows,
+    ArbitratedEscrows,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Reward {
     target_account_id: AccountId,
@@ -54,6 +163,11 @@ pub struct Reward {
     memo: Option<String>,
 }
 
+const MAX_REWARD_BATCH_ID_LENGTH: usize = 64;
+
+/// The number of accounts `ft_top_holders` scans by default, to stay within the view gas limit.
+const MAX_TOP_HOLDERS_SCAN_LIMIT: u64 = 500;
+
 const DATA_IMAGE_SVG_NEAR_ICON: &str = r#"data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 2000 2000' width='2000' height='2000'%3E%3Cg%3E%3Cg fill='%23eda735'%3E%3Cpath d='M858.44,9.87c197.07-27.87,402.41,3.5,581,91.48,203.21,99,370.36,268.93,466,473.26,86.39,182.58,114.19,391.53,81,590.48-33.18,203.07-131.84,394.65-277.46,540.37-139.73,141.09-322.78,239.19-518.1,276.43-202.08,39-416.31,14.87-603.61-70.61-208.84-94.1-382.75-262.44-483.79-467.63-94.91-190.08-125.21-410.9-88-619.72C50.36,623.84,149,435.89,293,292.55,444.26,140.59,645.84,39.49,858.44,9.87m-0.38,224.94c-176.79,32-340.56,128.34-455.25,266.31C291.76,633.21,227,803,222.39,975.38c-5.51,161.71,40.94,324.67,131.84,458.76,104,155.59,266.31,271.68,448,318.55,187.06,49.36,392.64,26.12,563.17-65.48,171.16-90.6,305.88-247.19,369.11-430,63.35-180,57.34-383.28-17.15-559-68.49-164-195.45-302.8-352.83-385.78C1211,230.06,1029.22,203.19,858.06,234.81Z'/%3E%3Cpath d='m693.54 501q101.79-.37 203.58 0c.13 101-.25 201.83.13 302.8 79 .37 157.88-.12 236.89.25q.19 63.92 0 128c-79 .25-158-.12-236.89.25q-.38 63.55 0 127.09c78.75-.12 157.51.12 236.26-.12 1.13 42.61.63 85.23.75 128-79 .62-158.13 0-237.14.37-.13 47-.13 94.1.13 141.09q252.6.37 505.08.12c.38 56.74.25 113.47.13 170.33-236.26-.12-472.65.25-708.91-.25q0-155.59 0-311.3c-32.05 0-64.11-.25-96.16-.25q-.19-63.92.13-127.84c31.93-.12 64-.25 95.91-.12q.38-63.73 0-127.22c-32.05.12-64-.12-96-.25-.13-42.61-.13-85.1-.13-127.72 32.05-.12 64.11-.37 96.16-.37.33-100.91.08-201.86.08-302.86'/%3E%3C/g%3E%3Cpath d='m858.06 234.81c171.16-31.62 353-4.75 506.46 77.61 157.38 83 284.34 221.82 352.83 385.78 74.5 175.71 80.51 379 17.15 559-63.23 182.83-197.95 339.42-369.11 430-170.53 91.6-376.12 114.85-563.17 65.48-181.67-46.86-343.94-163-448-318.55-90.88-134.13-137.34-297.04-131.83-458.75 4.61-172.38 69.37-342.17 180.42-474.26 114.69-138 278.46-234.32 455.25-266.31m-164.52 266.19c0 101 .25 202-.13 302.8-32.05 0-64.11.25-96.16.37 0 42.61 0 85.1.13 127.72 32.05.12 64 .37 96 .25q.38 63.55 0 127.22c-31.93-.12-64 0-95.91.12q-.38 63.92-.13 127.84c32.05 0 64.11.25 96.16.25q.19 155.59 0 311.3c236.26.5 472.65.12 708.91.25.13-56.86.25-113.6-.13-170.33q-252.6 0-505.08-.12c-.25-47-.25-94.1-.13-141.09 79-.37 158.13.25 237.14-.37-.13-42.74.38-85.35-.75-128-78.75.25-157.51 0-236.26.12q-.38-63.55 0-127.09c78.88-.37 157.88 0 236.89-.25q.19-64.11 0-128c-79-.37-157.88.12-236.89-.25-.38-101 0-201.83-.13-302.8q-101.74-.32-203.53.06' fill='%23231f20'/%3E%3C/g%3E%3C/svg%3E"#;
 
 #[near_bindgen]
@@ -61,8 +175,12 @@ impl Contract {
     /// Initializes the contract with the given total supply owned by the given `owner_id` with
     /// default metadata (for example purposes only).
     #[init]
-    pub fn new_default_meta(reward_operators: std::collections::HashSet<AccountId>) -> Self {
+    pub fn new_default_meta(
+        owner_id: AccountId,
+        reward_operators: std::collections::HashSet<AccountId>,
+    ) -> Self {
         Self::new(
+            owner_id,
             FungibleTokenMetadata {
                 spec: FT_METADATA_SPEC.to_string(),
                 name: "Q-bit LOL-coin".to_string(),
@@ -80,55 +198,134 @@ impl Contract {
     /// the given fungible token metadata.
     #[init]
     pub fn new(
+        owner_id: AccountId,
         metadata: FungibleTokenMetadata,
         reward_operators: std::collections::HashSet<AccountId>,
     ) -> Self {
         metadata.assert_valid();
-        Self {
-            token: FungibleToken::new(b"a".to_vec()),
+        migrate::write_state_version();
+        let mut this = Self {
+            owner_id,
+            token: FungibleToken::new(
+                b"a".to_vec(),
+                StorageKey::FrozenAccounts,
+                StorageKey::Seasons,
+                StorageKey::AccountSeasons,
+                StorageKey::AccountHistory,
+                StorageKey::Snapshots,
+                StorageKey::AccountSnapshots,
+                StorageKey::AccountLatestSnapshotIds,
+            ),
             metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
             reward_operators,
-        }
+            reward_operator_quotas: LookupMap::new(StorageKey::RewardOperatorQuotas),
+            pause_flags: PauseFlags::default(),
+            applied_reward_batches: LookupSet::new(StorageKey::AppliedRewardBatches),
+            vesting_grants: LookupMap::new(StorageKey::VestingGrants),
+            next_vesting_grant_id: 0,
+            allowances: LookupMap::new(StorageKey::Allowances),
+            allowance_spenders: LookupMap::new(StorageKey::AllowanceSpenders),
+            intent_nonces: LookupMap::new(StorageKey::IntentNonces),
+            multisig_policy: None,
+            reward_proposals: UnorderedMap::new(StorageKey::RewardProposals),
+            next_reward_proposal_id: 0,
+            polls: Vector::new(StorageKey::Polls),
+            poll_votes: LookupMap::new(StorageKey::PollVotes),
+            storage_sponsors: LookupMap::new(StorageKey::StorageSponsors),
+            operator_storage_deposits: LookupMap::new(StorageKey::OperatorStorageDeposits),
+            pool_sponsored_registrations: 0,
+            max_pool_sponsored_registrations: None,
+            staking_terms: vec![],
+            stakes: LookupMap::new(StorageKey::Stakes),
+            next_stake_id: 0,
+            total_staked: 0,
+            reward_schedules: UnorderedMap::new(StorageKey::RewardSchedules),
+            next_reward_schedule_id: 0,
+            reward_payouts_cursor: 0,
+            streams: LookupMap::new(StorageKey::Streams),
+            account_streams: LookupMap::new(StorageKey::AccountStreams),
+            received_streams: LookupMap::new(StorageKey::ReceivedStreams),
+            next_stream_id: 0,
+            escrows: LookupMap::new(StorageKey::Escrows),
+            account_escrows: LookupMap::new(StorageKey::AccountEscrows),
+            received_escrows: LookupMap::new(StorageKey::ReceivedEscrows),
+            arbitrated_escrows: LookupMap::new(StorageKey::ArbitratedEscrows),
+            next_escrow_id: 0,
+        };
+        this.measure_account_storage_usage();
+        this
     }
 
-    pub fn reset_metadata(&mut self) {
-        self.metadata.replace(&FungibleTokenMetadata {
-            spec: FT_METADATA_SPEC.to_string(),
-            name: "Q-bit LOL-coin".to_string(),
-            symbol: "ЛОЛ".to_string(),
-            icon: Some(DATA_IMAGE_SVG_NEAR_ICON.to_string()),
-            reference: None,
-            reference_hash: None,
-            decimals: 2,
-        });
+    /// Replaces the token metadata. Can only be called by the owner.
+    pub fn set_metadata(&mut self, metadata: FungibleTokenMetadata) {
+        self.assert_owner();
+        self.internal_update_metadata(&["*"], |current_metadata| *current_metadata = metadata);
     }
 
-    #[private]
-    pub fn reset_state() {
-        near_sdk::env::state_write(&Self::new_default_meta(std::collections::HashSet::from([
-            "lolcoin.qbit.near".parse().unwrap(),
-            "qbit.near".parse().unwrap(),
-            "frol.near".parse().unwrap(),
-        ])));
+    /// Replaces the token icon (a data URL), keeping the rest of the metadata intact. Can only be
+    /// called by the owner.
+    pub fn set_metadata_icon(&mut self, icon: Option<String>) {
+        self.assert_owner();
+        self.internal_update_metadata(&["icon"], |metadata| metadata.icon = icon);
     }
 
-    pub fn reward(&mut self, rewards: Vec<Reward>, memo: Option<String>) {
+    /// Replaces the link to the off-chain token metadata along with its base64-encoded sha256
+    /// hash, keeping the rest of the metadata intact. Can only be called by the owner.
+    pub fn set_metadata_reference(
+        &mut self,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+    ) {
+        self.assert_owner();
+        self.internal_update_metadata(&["reference", "reference_hash"], |metadata| {
+            metadata.reference = reference;
+            metadata.reference_hash = reference_hash;
+        });
+    }
+
+    /// Mints the tokens to the reward targets, registering the new accounts. Can only be called by
+    /// reward operators.
+    ///
+    /// `batch_id` is an optional idempotency key: a batch with the same id is applied only once,
+    /// so the call can be safely retried.
+    ///
+    /// If the total amount exceeds the threshold of the multisig policy, the rewards are not minted
+    /// until other reward operators approve them, and the id of the created proposal is returned.
+    pub fn reward(
+        &mut self,
+        rewards: Vec<Reward>,
+        memo: Option<String>,
+        batch_id: Option<String>,
+    ) -> Option<U64> {
         self.assert_reward_operator();
-        let mut events = vec![];
-        for reward in &rewards {
-            if self.token.accounts.get(&reward.target_account_id).is_none() {
-                self.token
-                    .internal_register_account(&reward.target_account_id);
+        self.assert_not_paused(PausableFeature::Rewards);
+        if let Some(batch_id) = &batch_id {
+            require!(
+                batch_id.len() <= MAX_REWARD_BATCH_ID_LENGTH,
+                "The batch id is too long"
+            );
+            if !self.applied_reward_batches.insert(batch_id) {
+                env::panic_str(&format!(
+                    "The reward batch {} has already been applied",
+                    batch_id
+                ));
             }
-            self.token
-                .internal_deposit(&reward.target_account_id, reward.tokens_amount.into());
-            events.push(near_contract_standards::fungible_token::events::FtMint {
-                owner_id: &reward.target_account_id,
-                amount: &reward.tokens_amount,
-                memo: reward.memo.as_deref().or_else(|| memo.as_deref()),
-            });
         }
-        near_contract_standards::fungible_token::events::FtMint::emit_many(&events);
+        let total_amount = rewards.iter().fold(0, |total: Balance, reward| {
+            total
+                .checked_add(reward.tokens_amount.0)
+                .unwrap_or_else(|| env::panic_str("Reward amount overflow"))
+        });
+        if self.internal_requires_multisig(total_amount) {
+            return Some(self.internal_create_reward_proposal(
+                rewards,
+                memo,
+                batch_id,
+                total_amount,
+            ));
+        }
+        self.internal_reward(&env::predecessor_account_id(), &rewards, memo.as_deref());
+        None
     }
 
     pub fn custodial_ft_transfer(
@@ -139,23 +336,237 @@ impl Contract {
         memo: Option<String>,
     ) {
         self.assert_reward_operator();
+        self.assert_not_paused(PausableFeature::CustodialTransfers);
+        self.assert_not_contract_account(&sender_id);
+        self.internal_claim_vested_before_spending(&sender_id);
         self.token
-            .internal_transfer(&sender_id, &receiver_id, amount.into(), memo);
+            .internal_custodial_transfer(&sender_id, &receiver_id, amount.into(), memo);
+    }
+
+    /// Returns whether the reward batch with the given idempotency key has already been applied.
+    pub fn is_reward_batch_applied(&self, batch_id: String) -> bool {
+        self.applied_reward_batches.contains(&batch_id)
     }
 
-    pub fn ft_balances(&self) -> Vec<(AccountId, U128)> {
+    /// Destroys `amount` of the caller's tokens, reducing the total supply.
+    /// Exactly 1 yoctoNEAR must be attached.
+    #[payable]
+    pub fn ft_burn(&mut self, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_not_paused(PausableFeature::Transfers);
+        let account_id = env::predecessor_account_id();
+        self.internal_claim_vested_before_spending(&account_id);
         self.token
-            .accounts
-            .iter()
-            .map(|(k, v)| (k, v.into()))
+            .internal_burn(&account_id, amount.into(), memo.as_deref());
+    }
+
+    /// Transfers the caller's tokens to multiple receivers atomically: either all the
+    /// `(receiver_id, amount, memo)` transfers succeed or none. Exactly 1 yoctoNEAR must be
+    /// attached.
+    #[payable]
+    pub fn ft_transfer_batch(&mut self, transfers: Vec<(AccountId, U128, Option<String>)>) {
+        assert_one_yocto();
+        self.assert_not_paused(PausableFeature::Transfers);
+        let sender_id = env::predecessor_account_id();
+        self.internal_claim_vested_before_spending(&sender_id);
+        self.token.internal_transfer_batch(&sender_id, &transfers);
+    }
+
+    /// Transfers the whole balance of the caller to `beneficiary_id` and unregisters the caller,
+    /// refunding the storage deposit along with the storage of the allowances granted by the caller.
+    /// The accounts with unclaimed vesting grants, active stakes, streams or escrows cannot be
+    /// closed.
+    /// Exactly 1 yoctoNEAR must be attached.
+    ///
+    /// Returns the transferred balance.
+    #[payable]
+    pub fn close_account(&mut self, beneficiary_id: AccountId) -> U128 {
+        assert_one_yocto();
+        self.assert_not_paused(PausableFeature::Transfers);
+        let account_id = env::predecessor_account_id();
+        self.internal_claim_vested_before_spending(&account_id);
+        self.assert_can_unregister(&account_id);
+        self.token.internal_settle_season(&account_id);
+        let balance = self.token.internal_unwrap_balance_of(&account_id);
+        if balance > 0 {
+            self.token.internal_transfer(
+                &account_id,
+                &beneficiary_id,
+                balance,
+                Some("account closure".to_string()),
+            );
+        }
+        self.token.internal_storage_unregister(None);
+        self.internal_refund_storage_deposit(&account_id);
+        self.internal_remove_allowances(&account_id);
+        AccountClosed {
+            account_id: &account_id,
+            beneficiary_id: &beneficiary_id,
+            balance: &balance.into(),
+        }
+        .emit();
+        balance.into()
+    }
+
+    /// Destroys `amount` of tokens held by `account_id`, e.g. when the holder spends them on a
+    /// privilege. Can only be called by reward operators.
+    pub fn burn_from(&mut self, account_id: AccountId, amount: U128, memo: Option<String>) {
+        self.assert_reward_operator();
+        self.assert_not_paused(PausableFeature::CustodialTransfers);
+        self.assert_not_contract_account(&account_id);
+        self.internal_claim_vested_before_spending(&account_id);
+        self.token
+            .internal_burn(&account_id, amount.into(), memo.as_deref());
+    }
+
+    /// Claws back `amount` of tokens erroneously minted to `account_id` by the reward referenced
+    /// with `reward_memo` (the memo or id of the original mint), reducing the total supply. Frozen
+    /// accounts can be clawed back as well.
+    ///
+    /// If the account has spent a part of the reward, the call fails unless `allow_partial` is
+    /// set, in which case the whole remaining balance is revoked.
+    ///
+    /// Returns the revoked amount. Can only be called by reward operators.
+    pub fn revoke_reward(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        reward_memo: String,
+        allow_partial: Option<bool>,
+    ) -> U128 {
+        self.assert_reward_operator();
+        self.assert_not_paused(PausableFeature::CustodialTransfers);
+        self.assert_not_contract_account(&account_id);
+        self.internal_claim_vested_before_spending(&account_id);
+        self.token.internal_settle_season(&account_id);
+        let balance = self.token.internal_unwrap_balance_of(&account_id);
+        let amount = if allow_partial.unwrap_or(false) {
+            std::cmp::min(amount.0, balance)
+        } else {
+            amount.0
+        };
+        require!(amount > 0, "There is nothing to revoke");
+        self.token.internal_force_withdraw(&account_id, amount);
+        let memo = format!("clawback: {}", reward_memo);
+        self.token.internal_record_movement(
+            &account_id,
+            MovementKind::Burn,
+            amount,
+            None,
+            Some(&memo),
+        );
+        near_contract_standards::fungible_token::events::FtBurn {
+            owner_id: &account_id,
+            amount: &U128(amount),
+            memo: Some(&memo),
+        }
+        .emit();
+        amount.into()
+    }
+
+    /// Returns the balances of the registered accounts in the registration order, starting from
+    /// `from_index` (0 by default) and returning at most `limit` entries (all by default). The
+    /// contract account holding the locked tokens is skipped.
+    pub fn ft_balances(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<(AccountId, U128)> {
+        require!(limit != Some(0), "Cannot provide limit of 0.");
+        let account_ids = self.token.accounts.keys_as_vector();
+        let balances = self.token.accounts.values_as_vector();
+        let start = u64::try_from(from_index.map_or(0, |index| index.0)).unwrap_or(u64::MAX);
+        let end = start
+            .saturating_add(limit.unwrap_or(u64::MAX))
+            .min(account_ids.len());
+        let contract_id = env::current_account_id();
+        (start..end)
+            .filter_map(|index| {
+                let account_id = account_ids.get(index).unwrap();
+                if account_id == contract_id {
+                    return None;
+                }
+                let balance = self
+                    .token
+                    .internal_season_balance_of(&account_id, balances.get(index).unwrap());
+                Some((account_id, balance.into()))
+            })
+            .collect()
+    }
+
+    /// Returns up to `limit` accounts with the largest balances, sorted by balance in descending
+    /// order, among at most `scan_limit` accounts (`MAX_TOP_HOLDERS_SCAN_LIMIT` by default) in the
+    /// registration order starting from `from_index` (0 by default). The contract account holding
+    /// the locked tokens is skipped.
+    ///
+    /// To build the whole leaderboard, call it page by page (see `ft_accounts_count`) and merge
+    /// the results.
+    pub fn ft_top_holders(
+        &self,
+        limit: u64,
+        from_index: Option<U128>,
+        scan_limit: Option<u64>,
+    ) -> Vec<(AccountId, U128)> {
+        require!(limit != 0, "Cannot provide limit of 0.");
+        require!(scan_limit != Some(0), "Cannot provide scan limit of 0.");
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+        let account_ids = self.token.accounts.keys_as_vector();
+        let balances = self.token.accounts.values_as_vector();
+        let start = u64::try_from(from_index.map_or(0, |index| index.0)).unwrap_or(u64::MAX);
+        let end = start
+            .saturating_add(scan_limit.unwrap_or(MAX_TOP_HOLDERS_SCAN_LIMIT))
+            .min(account_ids.len());
+        // Min-heap of (balance, account_id) holding the largest balances seen so far.
+        let mut top_holders = std::collections::BinaryHeap::new();
+        let contract_id = env::current_account_id();
+        for index in start..end {
+            let account_id = account_ids.get(index).unwrap();
+            if account_id == contract_id {
+                continue;
+            }
+            let balance = self
+                .token
+                .internal_season_balance_of(&account_id, balances.get(index).unwrap());
+            top_holders.push(std::cmp::Reverse((balance, account_id)));
+            if top_holders.len() > limit {
+                top_holders.pop();
+            }
+        }
+        top_holders
+            .into_sorted_vec()
+            .into_iter()
+            .map(|std::cmp::Reverse((balance, account_id))| (account_id, balance.into()))
             .collect()
     }
+
+    /// Returns the number of registered accounts.
+    pub fn ft_accounts_count(&self) -> u64 {
+        self.token.accounts.len()
+    }
+
+    /// Returns the recent movements (mints, burns and transfers) of the account balance along with
+    /// their indexes, oldest first. Only the last `MAX_HISTORY_LENGTH` (10) movements are kept.
+    ///
+    /// Starts from `from_index` (the oldest kept movement by default) and returns at most `limit`
+    /// entries (all by default).
+    pub fn ft_history(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<u64>,
+    ) -> Vec<(U64, Movement)> {
+        require!(limit != Some(0), "Cannot provide limit of 0.");
+        self.token
+            .internal_history_of(&account_id, from_index.map(u64::from), limit)
+    }
 }
 
 #[near_bindgen]
 impl FungibleTokenCore for Contract {
     #[payable]
     fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        self.assert_not_paused(PausableFeature::Transfers);
+        self.internal_claim_vested_before_spending(&env::predecessor_account_id());
         self.token.ft_transfer(receiver_id, amount, memo)
     }
 
@@ -167,6 +578,8 @@ impl FungibleTokenCore for Contract {
         memo: Option<String>,
         msg: String,
     ) -> PromiseOrValue<U128> {
+        self.assert_not_paused(PausableFeature::Transfers);
+        self.internal_claim_vested_before_spending(&env::predecessor_account_id());
         self.token.ft_transfer_call(receiver_id, amount, memo, msg)
     }
 
@@ -174,8 +587,11 @@ impl FungibleTokenCore for Contract {
         self.token.ft_total_supply()
     }
 
+    /// Returns the balance of the account including the unlocked vesting tokens, which are claimed
+    /// automatically when the account spends them.
     fn ft_balance_of(&self, account_id: AccountId) -> U128 {
-        self.token.ft_balance_of(account_id)
+        let unclaimed_vested_balance = self.internal_unclaimed_vested_balance_of(&account_id);
+        (self.token.ft_balance_of(account_id).0 + unclaimed_vested_balance).into()
     }
 }
 
@@ -206,18 +622,35 @@ impl StorageManagement for Contract {
         account_id: Option<AccountId>,
         registration_only: Option<bool>,
     ) -> StorageBalance {
-        self.token.storage_deposit(account_id, registration_only)
+        self.assert_not_paused(PausableFeature::Storage);
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let is_registered = self.token.accounts.get(&account_id).is_some();
+        self.token
+            .storage_deposit(Some(account_id.clone()), registration_only);
+        if !is_registered {
+            self.internal_record_storage_sponsor(
+                &account_id,
+                StorageSponsor::User(env::predecessor_account_id()),
+            );
+        }
+        self.internal_storage_balance_of(&account_id).unwrap()
     }
 
     #[payable]
     fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
-        self.token.storage_withdraw(amount)
+        self.assert_not_paused(PausableFeature::Storage);
+        self.token.storage_withdraw(amount);
+        self.internal_storage_balance_of(&env::predecessor_account_id())
+            .unwrap()
     }
 
     #[payable]
     fn storage_unregister(&mut self, force: Option<bool>) -> bool {
-        #[allow(unused_variables)]
+        self.assert_not_paused(PausableFeature::Storage);
+        self.assert_can_unregister(&env::predecessor_account_id());
         if let Some((account_id, balance)) = self.token.internal_storage_unregister(force) {
+            self.internal_refund_storage_deposit(&account_id);
+            self.internal_remove_allowances(&account_id);
             log!("Closed @{} with {}", account_id, balance);
             true
         } else {
@@ -230,7 +663,7 @@ impl StorageManagement for Contract {
     }
 
     fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
-        self.token.storage_balance_of(account_id)
+        self.internal_storage_balance_of(&account_id)
     }
 }
 
@@ -248,6 +681,9 @@ mod tests {
     use near_sdk::{testing_env, Balance};
 
     use super::*;
+    use crate::operators::{RewardOperatorLimits, RewardOperatorQuotaView};
+    use crate::seasons::SeasonBalance;
+    use crate::vesting::VestingBalance;
 
     const TOTAL_SUPPLY: Balance = 0;
 
@@ -264,8 +700,10 @@ mod tests {
     fn test_new() {
         let mut context = get_context(accounts(1));
         testing_env!(context.build());
-        let contract =
-            Contract::new_default_meta(std::collections::HashSet::from([accounts(1).into()]));
+        let contract = Contract::new_default_meta(
+            accounts(0),
+            std::collections::HashSet::from([accounts(1).into()]),
+        );
         testing_env!(context.is_view(true).build());
         assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
         assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
@@ -283,8 +721,10 @@ mod tests {
     fn test_transfer() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract =
-            Contract::new_default_meta(std::collections::HashSet::from([accounts(2).into()]));
+        let mut contract = Contract::new_default_meta(
+            accounts(0),
+            std::collections::HashSet::from([accounts(2).into()]),
+        );
         testing_env!(context
             .storage_usage(near_sdk::env::storage_usage())
             .attached_deposit(contract.storage_balance_bounds().min.into())
@@ -306,6 +746,7 @@ mod tests {
                 memo: None,
             }],
             None,
+            None,
         );
 
         testing_env!(context
@@ -325,11 +766,1564 @@ mod tests {
         assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
         assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
         assert_eq!(
-            contract.ft_balances(),
+            contract.ft_balances(None, None),
             vec![
                 (accounts(1), transfer_amount.into()),
                 (accounts(2), 0.into()),
             ]
         );
     }
+
+    #[test]
+    fn test_reward_operators_management() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        assert!(contract.add_reward_operator(accounts(2)));
+        assert!(!contract.add_reward_operator(accounts(2)));
+        assert!(contract.remove_reward_operator(accounts(1)));
+        assert!(!contract.remove_reward_operator(accounts(1)));
+        assert_eq!(contract.reward_operators(), vec![accounts(2)]);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.reward(
+            vec![Reward {
+                target_account_id: accounts(3),
+                tokens_amount: 100.into(),
+                memo: None,
+            }],
+            None,
+            None,
+        );
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can only be called by the owner")]
+    fn test_add_reward_operator_by_non_owner() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.add_reward_operator(accounts(2));
+    }
+
+    #[test]
+    fn test_reward_operator_limits() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        let limits = RewardOperatorLimits {
+            max_reward_amount: Some(100.into()),
+            max_call_amount: None,
+            daily_budget: Some(150.into()),
+            weekly_budget: None,
+        };
+        contract.set_reward_operator_limits(accounts(1), Some(limits.clone()));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let reward = |amount: Balance| Reward {
+            target_account_id: accounts(2),
+            tokens_amount: amount.into(),
+            memo: None,
+        };
+        contract.reward(vec![reward(100)], None, None);
+        contract.reward(vec![reward(50)], None, None);
+        assert_eq!(
+            contract.reward_operator_limits(accounts(1)),
+            Some(RewardOperatorQuotaView {
+                limits: limits.clone(),
+                minted_last_day: 150.into(),
+                minted_last_week: 150.into(),
+            })
+        );
+
+        // The daily budget is replenished once the earlier mints leave the 24 hours window.
+        testing_env!(context.block_timestamp(25 * 3_600 * 1_000_000_000).build());
+        contract.reward(vec![reward(100)], None, None);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 250);
+        let quota = contract.reward_operator_limits(accounts(1)).unwrap();
+        assert_eq!(quota.minted_last_day.0, 100);
+        assert_eq!(quota.minted_last_week.0, 250);
+
+        // The limits survive the operator being removed and added back.
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.remove_reward_operator(accounts(1));
+        contract.add_reward_operator(accounts(1));
+        assert_eq!(
+            contract.reward_operator_limits(accounts(1)).unwrap().limits,
+            limits
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The operator daily budget is exhausted")]
+    fn test_reward_over_daily_budget() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.set_reward_operator_limits(
+            accounts(1),
+            Some(RewardOperatorLimits {
+                max_reward_amount: None,
+                max_call_amount: None,
+                daily_budget: Some(100.into()),
+                weekly_budget: None,
+            }),
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 60.into(),
+                    memo: None,
+                },
+                Reward {
+                    target_account_id: accounts(3),
+                    tokens_amount: 60.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    fn test_balances_enumeration() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        contract.reward(
+            [(1, 30), (2, 10), (3, 50), (4, 20)]
+                .into_iter()
+                .map(|(account_index, amount)| Reward {
+                    target_account_id: accounts(account_index),
+                    tokens_amount: U128(amount),
+                    memo: None,
+                })
+                .collect(),
+            None,
+            None,
+        );
+
+        assert_eq!(contract.ft_accounts_count(), 4);
+        assert_eq!(
+            contract.ft_balances(Some(1.into()), Some(2)),
+            vec![(accounts(2), 10.into()), (accounts(3), 50.into())]
+        );
+        assert_eq!(
+            contract.ft_balances(Some(3.into()), Some(2)),
+            vec![(accounts(4), 20.into())]
+        );
+        assert!(contract.ft_balances(Some(4.into()), None).is_empty());
+        assert_eq!(
+            contract.ft_top_holders(3, None, None),
+            vec![
+                (accounts(3), 50.into()),
+                (accounts(1), 30.into()),
+                (accounts(4), 20.into()),
+            ]
+        );
+        assert_eq!(
+            contract.ft_top_holders(1, Some(1.into()), Some(2)),
+            vec![(accounts(3), 50.into())]
+        );
+    }
+
+    #[test]
+    fn test_burn() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.reward(
+            vec![Reward {
+                target_account_id: accounts(2),
+                tokens_amount: 100.into(),
+                memo: None,
+            }],
+            None,
+            None,
+        );
+        contract.burn_from(accounts(2), 30.into(), Some("cinema ticket".to_string()));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.ft_burn(20.into(), None);
+
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 50);
+        assert_eq!(contract.ft_total_supply().0, 50);
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut token = FungibleToken::new(
+            b"a".to_vec(),
+            StorageKey::FrozenAccounts,
+            StorageKey::Seasons,
+            StorageKey::AccountSeasons,
+            StorageKey::AccountHistory,
+            StorageKey::Snapshots,
+            StorageKey::AccountSnapshots,
+            StorageKey::AccountLatestSnapshotIds,
+        );
+        for (account_id, balance) in [(accounts(1), 100), (accounts(2), 0), (accounts(3), 42)] {
+            token.internal_register_account(&account_id);
+            token.internal_deposit(&account_id, balance);
+        }
+        let token = migrate::FungibleTokenV1 {
+            accounts: token.accounts,
+            total_supply: token.total_supply,
+            account_storage_usage: token.account_storage_usage,
+        };
+        let metadata = FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Q-bit LOL-coin".to_string(),
+            symbol: "ЛОЛ".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 2,
+        };
+        env::state_write(&migrate::ContractV1 {
+            token,
+            metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
+            reward_operators: std::collections::HashSet::from([accounts(4)]),
+        });
+
+        let contract: Contract = Contract::migrate(Some(accounts(5)));
+        env::state_write(&contract);
+        let contract: Contract = env::state_read().unwrap();
+
+        assert_eq!(contract.state_version(), migrate::STATE_VERSION);
+        assert_eq!(contract.owner_id(), accounts(5));
+        assert_eq!(contract.reward_operators(), vec![accounts(4)]);
+        assert_eq!(contract.ft_metadata().name, metadata.name);
+        assert_eq!(contract.ft_total_supply().0, 142);
+        assert_eq!(
+            contract.ft_balances(None, None),
+            vec![
+                (accounts(1), 100.into()),
+                (accounts(2), 0.into()),
+                (accounts(3), 42.into()),
+            ]
+        );
+        // The accounts registered before the sponsorship ledger have no deposit to refund.
+        assert_eq!(contract.storage_balance_of(accounts(1)).unwrap().total.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract state is already up to date")]
+    fn test_migrate_up_to_date_state() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(0), Default::default());
+        env::state_write(&contract);
+        Contract::migrate(None);
+    }
+
+    #[test]
+    fn test_metadata_updates() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), Default::default());
+        contract.set_metadata_icon(Some("data:image/svg+xml,2024".to_string()));
+        contract.set_metadata_reference(
+            Some("https://lolcoin.qbit.org.ua/metadata.json".to_string()),
+            Some(vec![1; 32].into()),
+        );
+
+        let metadata = contract.ft_metadata();
+        assert_eq!(metadata.name, "Q-bit LOL-coin");
+        assert_eq!(metadata.icon.as_deref(), Some("data:image/svg+xml,2024"));
+        assert_eq!(
+            metadata.reference.as_deref(),
+            Some("https://lolcoin.qbit.org.ua/metadata.json")
+        );
+
+        contract.set_metadata(FungibleTokenMetadata {
+            name: "Q-bit LOL-coin 2024".to_string(),
+            ..metadata
+        });
+        assert_eq!(contract.ft_metadata().name, "Q-bit LOL-coin 2024");
+    }
+
+    #[test]
+    #[should_panic(expected = "Rewards are paused")]
+    fn test_pause_rewards() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        let pause_flags = PauseFlags {
+            rewards: true,
+            ..Default::default()
+        };
+        contract.set_pause_flags(pause_flags);
+        assert_eq!(contract.pause_flags(), pause_flags);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.reward(
+            vec![Reward {
+                target_account_id: accounts(2),
+                tokens_amount: 100.into(),
+                memo: None,
+            }],
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The account charlie is frozen")]
+    fn test_frozen_account_custodial_transfer() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.freeze_account(accounts(2), "Cheating investigation".to_string());
+        assert_eq!(
+            contract.frozen_accounts(None, None),
+            vec![(accounts(2), "Cheating investigation".to_string())]
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.reward(
+            vec![Reward {
+                target_account_id: accounts(2),
+                tokens_amount: 100.into(),
+                memo: None,
+            }],
+            None,
+            None,
+        );
+        contract.custodial_ft_transfer(accounts(2), accounts(1), 10.into(), None);
+    }
+
+    #[test]
+    fn test_revoke_reward() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 1_000.into(),
+                    memo: Some("olympiad-42".to_string()),
+                },
+                Reward {
+                    target_account_id: accounts(3),
+                    tokens_amount: 10.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+        contract.custodial_ft_transfer(accounts(2), accounts(3), 950.into(), None);
+
+        let revoked = contract.revoke_reward(
+            accounts(2),
+            900.into(),
+            "olympiad-42".to_string(),
+            Some(true),
+        );
+        assert_eq!(revoked.0, 50);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
+        assert_eq!(contract.ft_total_supply().0, 960);
+    }
+
+    #[test]
+    #[should_panic(expected = "The reward batch 2023-10-week-1 has already been applied")]
+    fn test_reward_batch_idempotency() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        let rewards = || {
+            vec![Reward {
+                target_account_id: accounts(2),
+                tokens_amount: 100.into(),
+                memo: None,
+            }]
+        };
+        assert!(!contract.is_reward_batch_applied("2023-10-week-1".to_string()));
+        contract.reward(rewards(), None, Some("2023-10-week-1".to_string()));
+        assert!(contract.is_reward_batch_applied("2023-10-week-1".to_string()));
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 100);
+        contract.reward(rewards(), None, Some("2023-10-week-1".to_string()));
+    }
+
+    #[test]
+    fn test_vesting_grant() {
+        const MONTH: u64 = 30 * 24 * 3_600 * 1_000_000_000;
+        let mut context = get_context(accounts(1));
+        testing_env!(context.block_timestamp(MONTH).build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.create_vesting_grant(
+            accounts(2),
+            1_000.into(),
+            None,
+            MONTH.into(),
+            4,
+            Some("End of term prize".to_string()),
+        );
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
+        assert_eq!(contract.ft_total_supply().0, 0);
+
+        testing_env!(context
+            .block_timestamp(3 * MONTH + 1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        assert_eq!(
+            contract.ft_vesting_balance_of(accounts(2)),
+            VestingBalance {
+                locked: 500.into(),
+                unlocked: 500.into(),
+            }
+        );
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 500);
+        assert_eq!(contract.claim_vested().0, 500);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 500);
+
+        // Spending the balance claims the unlocked tokens first.
+        testing_env!(context
+            .block_timestamp(10 * MONTH)
+            .attached_deposit(1)
+            .build());
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 1_000);
+        contract.ft_burn(900.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 100);
+        assert_eq!(contract.ft_total_supply().0, 100);
+        assert!(contract.vesting_grants(accounts(2)).is_empty());
+    }
+
+    #[test]
+    fn test_burn_from_unclaimed_vested_balance() {
+        const MONTH: u64 = 30 * 24 * 3_600 * 1_000_000_000;
+        let mut context = get_context(accounts(1));
+        testing_env!(context.block_timestamp(MONTH).build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.create_vesting_grant(accounts(2), 1_000.into(), None, MONTH.into(), 4, None);
+
+        // The operators can debit the unlocked tokens reported by `ft_balance_of`.
+        testing_env!(context.block_timestamp(10 * MONTH).build());
+        contract.burn_from(accounts(2), 600.into(), None);
+        assert_eq!(
+            contract
+                .revoke_reward(accounts(2), 400.into(), "prize".to_string(), None)
+                .0,
+            400
+        );
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
+        assert_eq!(contract.ft_total_supply().0, 0);
+    }
+
+    #[test]
+    fn test_seasons() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        let reward = |account_index: usize, amount: u128| Reward {
+            target_account_id: accounts(account_index),
+            tokens_amount: amount.into(),
+            memo: None,
+        };
+        contract.reward(vec![reward(1, 100), reward(2, 100)], None, None);
+
+        // The balances of the first season are converted at 50%.
+        assert_eq!(contract.close_season(5_000), 1);
+        contract.reward(vec![reward(2, 10), reward(3, 10)], None, None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 50);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 60);
+        assert_eq!(
+            contract.ft_season_balance_of(accounts(1)),
+            Some(SeasonBalance {
+                season_id: 0,
+                balance: 100.into(),
+                current_season_balance: 50.into(),
+            })
+        );
+        assert_eq!(contract.seasons(None, None)[0].outstanding_supply.0, 100);
+        assert_eq!(contract.ft_total_supply().0, 120);
+
+        // The balances of the second season expire.
+        assert_eq!(contract.close_season(0), 2);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
+        assert_eq!(contract.ft_total_supply().0, 0);
+        contract.settle_seasons(vec![accounts(1), accounts(2), accounts(3)]);
+        assert_eq!(contract.ft_total_supply().0, 0);
+        let seasons = contract.seasons(None, None);
+        assert_eq!(seasons.len(), 3);
+        assert!(seasons
+            .iter()
+            .all(|season| season.outstanding_supply.0 == 0));
+    }
+
+    #[test]
+    fn test_allowance() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(1),
+                    tokens_amount: 100.into(),
+                    memo: None,
+                },
+                Reward {
+                    target_account_id: accounts(3),
+                    tokens_amount: 0.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(near_sdk::ONE_NEAR)
+            .build());
+        contract.ft_approve(accounts(2), 60.into());
+        assert_eq!(contract.ft_allowance(accounts(1), accounts(2)).0, 60);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.ft_transfer_from(accounts(1), accounts(3), 40.into(), None);
+        assert_eq!(contract.ft_allowance(accounts(1), accounts(2)).0, 20);
+        contract.ft_transfer_from(accounts(1), accounts(3), 20.into(), None);
+        assert_eq!(contract.ft_allowance(accounts(1), accounts(2)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 40);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 60);
+    }
+
+    #[test]
+    fn test_signed_custodial_transfer() {
+        use crate::intents::TransferIntent;
+        use ed25519_dalek::Signer;
+
+        let secret_key = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+        let keypair = ed25519_dalek::Keypair {
+            public: (&secret_key).into(),
+            secret: secret_key,
+        };
+        let student_id: AccountId = keypair
+            .public
+            .as_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()
+            .parse()
+            .unwrap();
+
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: student_id.clone(),
+                    tokens_amount: 100.into(),
+                    memo: None,
+                },
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 0.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+
+        let intent = TransferIntent {
+            contract_id: env::current_account_id(),
+            sender_id: student_id.clone(),
+            receiver_id: accounts(2),
+            amount: 30.into(),
+            memo: None,
+            nonce: 1.into(),
+            expires_at: (env::block_timestamp() + 1).into(),
+        };
+        let signature = keypair.sign(&intent.try_to_vec().unwrap());
+        contract.signed_custodial_ft_transfer(intent.clone(), signature.to_bytes().to_vec().into());
+        assert_eq!(contract.ft_balance_of(student_id.clone()).0, 70);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 30);
+        assert_eq!(contract.ft_intent_nonce(student_id).0, 1);
+
+        // The custodian cannot alter the signed intent.
+        let forged_intent = TransferIntent {
+            amount: 70.into(),
+            nonce: 2.into(),
+            ..intent
+        };
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            contract
+                .signed_custodial_ft_transfer(forged_intent, signature.to_bytes().to_vec().into())
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_history() {
+        use crate::fungible_token::history::MAX_HISTORY_LENGTH;
+
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 100.into(),
+                    memo: Some("olympiad".to_string()),
+                },
+                Reward {
+                    target_account_id: accounts(3),
+                    tokens_amount: 0.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+        contract.custodial_ft_transfer(accounts(2), accounts(3), 10.into(), None);
+        contract.burn_from(accounts(2), 5.into(), Some("privilege".to_string()));
+
+        let history = contract.ft_history(accounts(2), None, None);
+        assert_eq!(
+            history
+                .iter()
+                .map(|(index, movement)| (
+                    index.0,
+                    movement.kind.clone(),
+                    movement.amount.0,
+                    movement.counterparty_id.clone(),
+                    movement.memo.clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    0,
+                    MovementKind::Mint,
+                    100,
+                    None,
+                    Some("olympiad".to_string())
+                ),
+                (
+                    1,
+                    MovementKind::CustodialTransferOut,
+                    10,
+                    Some(accounts(3)),
+                    None
+                ),
+                (
+                    2,
+                    MovementKind::Burn,
+                    5,
+                    None,
+                    Some("privilege".to_string())
+                ),
+            ]
+        );
+        assert_eq!(
+            contract.ft_history(accounts(3), None, None)[1].1.kind,
+            MovementKind::CustodialTransferIn
+        );
+
+        // Only the most recent movements are kept.
+        testing_env!(context.block_index(100).build());
+        for _ in 0..MAX_HISTORY_LENGTH {
+            contract.custodial_ft_transfer(accounts(2), accounts(3), 1.into(), None);
+        }
+        let history = contract.ft_history(accounts(2), None, None);
+        assert_eq!(history.len() as u64, MAX_HISTORY_LENGTH);
+        assert_eq!(history[0].0 .0, 3);
+        assert_eq!(history[0].1.block_height.0, 100);
+        let page = contract.ft_history(accounts(2), Some(10.into()), Some(2));
+        assert_eq!(
+            page.iter().map(|(index, _)| index.0).collect::<Vec<_>>(),
+            vec![10, 11]
+        );
+
+        // The memos are truncated to 32 bytes.
+        contract.custodial_ft_transfer(accounts(2), accounts(3), 1.into(), Some("ЛОЛ".repeat(20)));
+        let history = contract.ft_history(accounts(2), None, None);
+        assert_eq!(
+            history.last().unwrap().1.memo.as_deref(),
+            Some("ЛОЛ".repeat(20)[..32].as_ref())
+        );
+    }
+
+    #[test]
+    fn test_reward_proposals() {
+        use crate::proposals::MultisigPolicy;
+
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(
+            accounts(0),
+            std::collections::HashSet::from([accounts(1), accounts(4), accounts(5)]),
+        );
+        contract.set_multisig_policy(Some(MultisigPolicy {
+            threshold: 100.into(),
+            required_approvals: 2,
+            proposal_duration: 1_000.into(),
+        }));
+        let reward = |amount: u128| {
+            vec![Reward {
+                target_account_id: accounts(2),
+                tokens_amount: amount.into(),
+                memo: None,
+            }]
+        };
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        assert_eq!(contract.reward(reward(100), None, None), None);
+        let proposal_id = contract
+            .reward(reward(500), None, Some("olympiad".to_string()))
+            .unwrap();
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 100);
+        assert_eq!(contract.reward_proposals(None, None).len(), 1);
+
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        assert!(contract.approve_proposal(proposal_id));
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 600);
+        assert!(contract.reward_proposals(None, None).is_empty());
+        assert!(contract.is_reward_batch_applied("olympiad".to_string()));
+
+        // The proposal is discarded once the quorum cannot be reached.
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let proposal_id = contract
+            .reward(reward(500), None, Some("hackathon".to_string()))
+            .unwrap();
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        assert!(!contract.reject_proposal(proposal_id));
+        testing_env!(context.predecessor_account_id(accounts(5)).build());
+        assert!(contract.reject_proposal(proposal_id));
+        assert!(!contract.is_reward_batch_applied("hackathon".to_string()));
+
+        // The expired proposal is discarded instead of being approved.
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let proposal_id = contract.reward(reward(500), None, None).unwrap();
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .block_timestamp(1_001)
+            .build());
+        assert!(!contract.approve_proposal(proposal_id));
+        assert_eq!(contract.reward_proposal(proposal_id), None);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 600);
+
+        // The new accounts are sponsored by the proposer rather than by the last approver.
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(contract.storage_balance_bounds().min.0)
+            .build());
+        contract.deposit_storage_sponsorship();
+        testing_env!(context.attached_deposit(0).build());
+        let proposal_id = contract
+            .reward(
+                vec![Reward {
+                    target_account_id: accounts(3),
+                    tokens_amount: 500.into(),
+                    memo: None,
+                }],
+                None,
+                None,
+            )
+            .unwrap();
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        assert!(contract.approve_proposal(proposal_id));
+        assert_eq!(
+            contract.storage_sponsor_of(accounts(3)),
+            Some(crate::sponsorship::StorageSponsor::Operator(accounts(1)))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount exceeds the multisig threshold")]
+    fn test_vesting_grant_over_multisig_threshold() {
+        use crate::proposals::MultisigPolicy;
+
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(
+            accounts(0),
+            std::collections::HashSet::from([accounts(0), accounts(1)]),
+        );
+        contract.set_multisig_policy(Some(MultisigPolicy {
+            threshold: 100.into(),
+            required_approvals: 2,
+            proposal_duration: 1_000.into(),
+        }));
+        contract.create_vesting_grant(accounts(2), 500.into(), None, 1.into(), 1, None);
+    }
+
+    #[test]
+    fn test_close_account() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 100.into(),
+                    memo: None,
+                },
+                Reward {
+                    target_account_id: accounts(3),
+                    tokens_amount: 5.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(near_sdk::ONE_NEAR)
+            .build());
+        contract.ft_approve(accounts(4), 10.into());
+
+        // The allowances granted by the closed account are revoked.
+        testing_env!(context.attached_deposit(1).build());
+        assert_eq!(contract.close_account(accounts(3)).0, 100);
+        assert!(contract.storage_balance_of(accounts(2)).is_none());
+        assert_eq!(contract.ft_allowance(accounts(2), accounts(4)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 105);
+        assert_eq!(contract.ft_total_supply().0, 105);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account has unclaimed vesting grants")]
+    fn test_storage_unregister_with_vesting_grant() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.create_vesting_grant(accounts(2), 100.into(), None, 1_000.into(), 1, None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.storage_unregister(Some(true));
+    }
+
+    #[test]
+    fn test_transfer_batch() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.reward(
+            [(accounts(2), 100), (accounts(3), 0), (accounts(4), 0)]
+                .into_iter()
+                .map(|(target_account_id, amount)| Reward {
+                    target_account_id,
+                    tokens_amount: amount.into(),
+                    memo: None,
+                })
+                .collect(),
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.ft_transfer_batch(vec![
+            (accounts(3), 30.into(), None),
+            (accounts(4), 50.into(), Some("thanks".to_string())),
+        ]);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 20);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 30);
+        assert_eq!(contract.ft_balance_of(accounts(4)).0, 50);
+
+        // A single event lists all the transfers of the batch.
+        let events: Vec<near_sdk::serde_json::Value> = near_sdk::test_utils::get_logs()
+            .iter()
+            .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+            .map(|event| near_sdk::serde_json::from_str(event).unwrap())
+            .filter(|event: &near_sdk::serde_json::Value| event["event"] == "ft_transfer")
+            .collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["data"].as_array().unwrap().len(), 2);
+        assert_eq!(events[0]["data"][1]["memo"], "thanks");
+    }
+
+    #[test]
+    #[should_panic(expected = "The account doesn't have enough balance")]
+    fn test_transfer_batch_over_balance() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.reward(
+            [(accounts(2), 20), (accounts(3), 0), (accounts(4), 0)]
+                .into_iter()
+                .map(|(target_account_id, amount)| Reward {
+                    target_account_id,
+                    tokens_amount: amount.into(),
+                    memo: None,
+                })
+                .collect(),
+            None,
+            None,
+        );
+
+        // The balance is checked for the total amount of the batch.
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.ft_transfer_batch(vec![
+            (accounts(3), 10.into(), None),
+            (accounts(4), 11.into(), None),
+        ]);
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        let reward = |account_id: AccountId, amount: u128| {
+            vec![Reward {
+                target_account_id: account_id,
+                tokens_amount: amount.into(),
+                memo: None,
+            }]
+        };
+        contract.reward(reward(accounts(1), 100), None, None);
+        let first_snapshot_id = contract.take_snapshot();
+
+        contract.reward(reward(accounts(1), 50), None, None);
+        contract.reward(reward(accounts(2), 30), None, None);
+        let second_snapshot_id = contract.take_snapshot();
+        contract.close_season(5_000);
+        contract.settle_seasons(vec![accounts(1)]);
+        let third_snapshot_id = contract.take_snapshot();
+        // Nothing is saved for the accounts that are not registered.
+        let storage_usage = env::storage_usage();
+        contract.settle_seasons(vec![accounts(5)]);
+        assert_eq!(env::storage_usage(), storage_usage);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.ft_transfer(accounts(2), 25.into(), None);
+
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 50);
+        assert_eq!(
+            contract.ft_balance_of_at(accounts(1), first_snapshot_id).0,
+            100
+        );
+        assert_eq!(
+            contract.ft_balance_of_at(accounts(2), first_snapshot_id).0,
+            0
+        );
+        assert_eq!(contract.ft_total_supply_at(first_snapshot_id).0, 100);
+        // The balances are reported in the season that was current at the snapshot.
+        assert_eq!(
+            contract.ft_balance_of_at(accounts(1), second_snapshot_id).0,
+            150
+        );
+        assert_eq!(
+            contract.ft_balance_of_at(accounts(2), second_snapshot_id).0,
+            30
+        );
+        assert_eq!(contract.ft_total_supply_at(second_snapshot_id).0, 180);
+        // The supply left from the closed season is converted even if it is not settled yet.
+        assert_eq!(
+            contract.ft_balance_of_at(accounts(2), third_snapshot_id).0,
+            15
+        );
+        assert_eq!(contract.ft_total_supply_at(third_snapshot_id).0, 90);
+    }
+
+    #[test]
+    fn test_polls() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(1),
+                    tokens_amount: 100.into(),
+                    memo: None,
+                },
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 30.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+        contract.take_snapshot();
+        let poll_id = contract.create_poll(
+            "Where should the class trip go?".to_string(),
+            vec!["Museum".to_string(), "Zoo".to_string()],
+            1_000.into(),
+            None,
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.vote(poll_id, 0);
+        contract.vote(poll_id, 1);
+        // The tokens received after the poll creation do not count.
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.ft_transfer(accounts(1), 30.into(), None);
+        contract.vote(poll_id, 0);
+
+        let poll = contract.poll(poll_id).unwrap();
+        assert_eq!(poll.tallies, vec![U128(30), U128(100)]);
+        assert_eq!(
+            contract
+                .poll_vote(poll_id, accounts(1))
+                .unwrap()
+                .option_index,
+            1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The option is too long")]
+    fn test_poll_option_too_long() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        let snapshot_id = contract.take_snapshot();
+        contract.create_poll(
+            "Where should the class trip go?".to_string(),
+            vec!["Museum".to_string(), "Zoo".repeat(100)],
+            1_000.into(),
+            Some(snapshot_id),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The storage sponsorship pool is exhausted")]
+    fn test_storage_sponsorship() {
+        use crate::sponsorship::StorageSponsor;
+
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.set_max_pool_sponsored_registrations(Some(1));
+        let storage_deposit = contract.storage_balance_bounds().min.0;
+        let reward = |account_id: AccountId| {
+            vec![Reward {
+                target_account_id: account_id,
+                tokens_amount: 0.into(),
+                memo: None,
+            }]
+        };
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(storage_deposit)
+            .build());
+        contract.deposit_storage_sponsorship();
+        contract.reward(reward(accounts(2)), None, None);
+        contract.reward(reward(accounts(3)), None, None);
+        assert_eq!(
+            contract.storage_sponsor_of(accounts(2)),
+            Some(StorageSponsor::Operator(accounts(1)))
+        );
+        assert_eq!(
+            contract.storage_sponsor_of(accounts(3)),
+            Some(StorageSponsor::Pool)
+        );
+        assert_eq!(contract.storage_sponsorship_balance_of(accounts(1)).0, 0);
+        // The sponsored accounts do not own their storage deposit.
+        assert_eq!(contract.storage_balance_of(accounts(2)).unwrap().total.0, 0);
+
+        // The storage deposit goes back to the sponsor.
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        assert!(contract.storage_unregister(None));
+        assert_eq!(
+            contract.storage_sponsorship_balance_of(accounts(1)).0,
+            storage_deposit
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        assert_eq!(
+            contract.withdraw_storage_sponsorship(None).0,
+            storage_deposit
+        );
+        contract.reward(reward(accounts(4)), None, None);
+    }
+
+    #[test]
+    fn test_staking() {
+        use crate::staking::StakingTerm;
+        const DAY: u64 = 24 * 3_600 * 1_000_000_000;
+
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        contract.set_staking_terms(vec![StakingTerm {
+            duration: (30 * DAY).into(),
+            interest_rate_bps: 1_000,
+        }]);
+        contract.reward(
+            vec![Reward {
+                target_account_id: accounts(1),
+                tokens_amount: 1_000.into(),
+                memo: None,
+            }],
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        let stake_id = contract.stake(400.into(), 0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 600);
+        assert_eq!(contract.total_staked().0, 400);
+        // The staked tokens are held by the contract account.
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 400);
+        assert_eq!(contract.ft_total_supply().0, 1_000);
+        assert_eq!(
+            contract.ft_top_holders(10, None, None),
+            vec![(accounts(1), 600.into())]
+        );
+
+        testing_env!(context.block_timestamp(15 * DAY).build());
+        assert_eq!(contract.stakes_of(accounts(1))[0].claimable_interest.0, 20);
+        assert_eq!(contract.claim_staking_rewards().0, 20);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 620);
+
+        testing_env!(context.block_timestamp(30 * DAY).build());
+        assert_eq!(contract.unstake(stake_id).0, 420);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_040);
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 0);
+        assert_eq!(contract.ft_total_supply().0, 1_040);
+        assert!(contract.stakes_of(accounts(1)).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot be applied to the contract account")]
+    fn test_burn_from_contract_account() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        contract.burn_from(accounts(0), 1.into(), None);
+    }
+
+    #[test]
+    fn test_reward_schedules() {
+        use crate::operators::RewardOperatorLimits;
+        const WEEK: u64 = 7 * 24 * 3_600 * 1_000_000_000;
+
+        let mut context = get_context(accounts(0));
+        testing_env!(context.block_timestamp(WEEK).build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.set_reward_operator_limits(
+            accounts(1),
+            Some(RewardOperatorLimits {
+                max_reward_amount: Some(10.into()),
+                max_call_amount: None,
+                daily_budget: None,
+                weekly_budget: None,
+            }),
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let schedule_id = contract.create_reward_schedule(
+            accounts(2),
+            10.into(),
+            WEEK.into(),
+            None,
+            Some(3),
+            Some("stipend".to_string()),
+        );
+
+        // Anyone can process the payouts.
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        assert_eq!(contract.process_due_payouts(None), 1);
+        assert_eq!(contract.process_due_payouts(None), 0);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 10);
+        assert_eq!(
+            contract
+                .reward_schedule(schedule_id)
+                .unwrap()
+                .next_payout_at
+                .0,
+            2 * WEEK
+        );
+
+        // The overdue installments are minted at once, up to the payouts count.
+        testing_env!(context.block_timestamp(10 * WEEK).build());
+        assert_eq!(contract.process_due_payouts(Some(2)), 2);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 30);
+        assert_eq!(contract.reward_schedule(schedule_id), None);
+        assert!(contract.reward_schedules(None, None).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The reward amount exceeds the operator limit")]
+    fn test_reward_schedule_over_operator_limit() {
+        use crate::operators::RewardOperatorLimits;
+        const WEEK: u64 = 7 * 24 * 3_600 * 1_000_000_000;
+
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(1)]));
+        contract.set_reward_operator_limits(
+            accounts(1),
+            Some(RewardOperatorLimits {
+                max_reward_amount: Some(10.into()),
+                max_call_amount: None,
+                daily_budget: None,
+                weekly_budget: None,
+            }),
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.create_reward_schedule(accounts(2), 20.into(), WEEK.into(), None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The total amount exceeds the multisig threshold")]
+    fn test_reward_schedule_over_multisig_threshold() {
+        use crate::proposals::MultisigPolicy;
+        const WEEK: u64 = 7 * 24 * 3_600 * 1_000_000_000;
+
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(
+            accounts(0),
+            std::collections::HashSet::from([accounts(0), accounts(1)]),
+        );
+        contract.set_multisig_policy(Some(MultisigPolicy {
+            threshold: 100.into(),
+            required_approvals: 2,
+            proposal_duration: 1_000.into(),
+        }));
+        // The schedule running until canceled has no upper bound on the total amount.
+        contract.create_reward_schedule(accounts(2), 10.into(), WEEK.into(), None, None, None);
+    }
+
+    #[test]
+    fn test_streams() {
+        const SECOND: u64 = 1_000_000_000;
+
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(1),
+                    tokens_amount: 1_000.into(),
+                    memo: None,
+                },
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 0.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(near_sdk::ONE_NEAR)
+            .build());
+        let stream_id = contract.create_stream(accounts(2), 2.into(), 100.into());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 900);
+        assert_eq!(contract.streams_of(accounts(2)).len(), 1);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .block_timestamp(10 * SECOND)
+            .build());
+        assert_eq!(contract.withdraw_from_stream(stream_id).0, 20);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 20);
+
+        // Nothing is streamed while the stream is paused.
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.set_stream_paused(stream_id, true);
+        testing_env!(context.block_timestamp(30 * SECOND).build());
+        contract.set_stream_paused(stream_id, false);
+        testing_env!(context.block_timestamp(35 * SECOND).build());
+        assert_eq!(contract.stream(stream_id).unwrap().withdrawable.0, 10);
+
+        contract.cancel_stream(stream_id);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 30);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 970);
+        assert_eq!(contract.ft_total_supply().0, 1_000);
+        assert!(contract.streams_of(accounts(1)).is_empty());
+        assert!(contract.streams_of(accounts(2)).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "yoctoNEAR to cover storage")]
+    fn test_create_stream_without_storage_deposit() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(1),
+                    tokens_amount: 100.into(),
+                    memo: None,
+                },
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 0.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.create_stream(accounts(2), 2.into(), 100.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "The account bob is frozen")]
+    fn test_withdraw_from_stream_of_frozen_sender() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(1),
+                    tokens_amount: 100.into(),
+                    memo: None,
+                },
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 0.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(near_sdk::ONE_NEAR)
+            .build());
+        let stream_id = contract.create_stream(accounts(2), 2.into(), 100.into());
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0)
+            .build());
+        contract.freeze_account(accounts(1), "stolen keys".to_string());
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .block_timestamp(10_000_000_000)
+            .build());
+        contract.withdraw_from_stream(stream_id);
+    }
+
+    #[test]
+    fn test_escrows() {
+        use crate::escrows::EscrowOutcome;
+
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(1),
+                    tokens_amount: 100.into(),
+                    memo: None,
+                },
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 0.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(near_sdk::ONE_NEAR)
+            .build());
+        let released_escrow_id = contract.create_escrow(
+            accounts(2),
+            accounts(0),
+            30.into(),
+            1_000.into(),
+            Some("tutoring".to_string()),
+        );
+        let disputed_escrow_id =
+            contract.create_escrow(accounts(2), accounts(0), 20.into(), 1_000.into(), None);
+        let expired_escrow_id =
+            contract.create_escrow(accounts(2), accounts(0), 10.into(), 1_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 40);
+        assert_eq!(contract.escrows_of(accounts(0)).len(), 3);
+
+        contract.release_escrow(released_escrow_id);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 30);
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.resolve_escrow(disputed_escrow_id, EscrowOutcome::Refunded);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 60);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(1_001)
+            .build());
+        contract.refund_escrow(expired_escrow_id);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 70);
+        assert!(contract.escrows_of(accounts(1)).is_empty());
+        assert_eq!(contract.ft_total_supply().0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account bob is frozen")]
+    fn test_release_escrow_of_frozen_payer() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(1),
+                    tokens_amount: 100.into(),
+                    memo: None,
+                },
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 0.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(near_sdk::ONE_NEAR)
+            .build());
+        let escrow_id =
+            contract.create_escrow(accounts(2), accounts(0), 30.into(), 1_000.into(), None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0)
+            .build());
+        contract.freeze_account(accounts(1), "stolen keys".to_string());
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.release_escrow(escrow_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The memo is too long")]
+    fn test_escrow_memo_too_long() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(0), std::collections::HashSet::from([accounts(0)]));
+        contract.reward(
+            vec![
+                Reward {
+                    target_account_id: accounts(1),
+                    tokens_amount: 100.into(),
+                    memo: None,
+                },
+                Reward {
+                    target_account_id: accounts(2),
+                    tokens_amount: 0.into(),
+                    memo: None,
+                },
+            ],
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(near_sdk::ONE_NEAR)
+            .build());
+        contract.create_escrow(
+            accounts(2),
+            accounts(0),
+            1.into(),
+            1_000.into(),
+            Some("a".repeat(1_000)),
+        );
+    }
 }
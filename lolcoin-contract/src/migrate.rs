@@ -0,0 +1,147 @@
+//! Versioned contract state layout.
+//!
+//! The version of the state layout is stored next to the contract state under
+//! [`STATE_VERSION_KEY`]. States written before the versioning was introduced have no version
+//! stored and are treated as version 1.
+//!
+//! To upgrade the contract, deploy the new code and call `migrate` in the same batch transaction.
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedMap, Vector};
+use near_sdk::{env, near_bindgen, require, AccountId, Balance, StorageUsage};
+
+use crate::fungible_token::metadata::FungibleTokenMetadata;
+use crate::fungible_token::FungibleToken;
+use crate::pause::PauseFlags;
+use crate::{Contract, ContractExt, StorageKey};
+
+/// The version of the state layout of the current [`Contract`].
+pub(crate) const STATE_VERSION: u32 = 2;
+
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+
+/// The `FungibleToken` layout of qbit-lolcoin-near 2.0.0, before the accounts could be frozen.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub(crate) struct FungibleTokenV1 {
+    pub(crate) accounts: UnorderedMap<AccountId, Balance>,
+    pub(crate) total_supply: Balance,
+    pub(crate) account_storage_usage: StorageUsage,
+}
+
+/// The state layout of qbit-lolcoin-near 2.0.0, before the contract got an owner.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub(crate) struct ContractV1 {
+    pub(crate) token: FungibleTokenV1,
+    pub(crate) metadata: LazyOption<FungibleTokenMetadata>,
+    pub(crate) reward_operators: std::collections::HashSet<AccountId>,
+}
+
+pub(crate) fn read_state_version() -> u32 {
+    env::storage_read(STATE_VERSION_KEY).map_or(1, |version| {
+        u32::try_from_slice(&version)
+            .unwrap_or_else(|_| env::panic_str("Cannot deserialize the state version"))
+    })
+}
+
+pub(crate) fn write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &STATE_VERSION.try_to_vec().unwrap());
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Upgrades the state written by a previous version of the contract to the current layout,
+    /// preserving all the balances.
+    ///
+    /// `owner_id` is only used when migrating from a layout without an owner and defaults to the
+    /// contract account itself.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(owner_id: Option<AccountId>) -> Self {
+        let state_version = read_state_version();
+        require!(
+            state_version != STATE_VERSION,
+            "The contract state is already up to date"
+        );
+        let contract = match state_version {
+            1 => {
+                let ContractV1 {
+                    token,
+                    metadata,
+                    reward_operators,
+                } = env::state_read()
+                    .unwrap_or_else(|| env::panic_str("The contract is not initialized"));
+                let FungibleTokenV1 {
+                    accounts,
+                    total_supply,
+                    account_storage_usage,
+                } = token;
+                let mut token = FungibleToken {
+                    accounts,
+                    total_supply,
+                    account_storage_usage,
+                    frozen_accounts: UnorderedMap::new(StorageKey::FrozenAccounts),
+                    seasons: Vector::new(StorageKey::Seasons),
+                    account_seasons: LookupMap::new(StorageKey::AccountSeasons),
+                    account_history: LookupMap::new(StorageKey::AccountHistory),
+                    snapshots: Vector::new(StorageKey::Snapshots),
+                    account_snapshots: LookupMap::new(StorageKey::AccountSnapshots),
+                    account_latest_snapshot_ids: LookupMap::new(
+                        StorageKey::AccountLatestSnapshotIds,
+                    ),
+                };
+                token.internal_open_season();
+                let mut contract = Self {
+                    owner_id: owner_id.unwrap_or_else(env::current_account_id),
+                    token,
+                    metadata,
+                    reward_operators,
+                    reward_operator_quotas: LookupMap::new(StorageKey::RewardOperatorQuotas),
+                    pause_flags: PauseFlags::default(),
+                    applied_reward_batches: LookupSet::new(StorageKey::AppliedRewardBatches),
+                    vesting_grants: LookupMap::new(StorageKey::VestingGrants),
+                    next_vesting_grant_id: 0,
+                    allowances: LookupMap::new(StorageKey::Allowances),
+                    allowance_spenders: LookupMap::new(StorageKey::AllowanceSpenders),
+                    intent_nonces: LookupMap::new(StorageKey::IntentNonces),
+                    multisig_policy: None,
+                    reward_proposals: UnorderedMap::new(StorageKey::RewardProposals),
+                    next_reward_proposal_id: 0,
+                    polls: Vector::new(StorageKey::Polls),
+                    poll_votes: LookupMap::new(StorageKey::PollVotes),
+                    storage_sponsors: LookupMap::new(StorageKey::StorageSponsors),
+                    operator_storage_deposits: LookupMap::new(StorageKey::OperatorStorageDeposits),
+                    pool_sponsored_registrations: 0,
+                    max_pool_sponsored_registrations: None,
+                    staking_terms: vec![],
+                    stakes: LookupMap::new(StorageKey::Stakes),
+                    next_stake_id: 0,
+                    total_staked: 0,
+                    reward_schedules: UnorderedMap::new(StorageKey::RewardSchedules),
+                    next_reward_schedule_id: 0,
+                    reward_payouts_cursor: 0,
+                    streams: LookupMap::new(StorageKey::Streams),
+                    account_streams: LookupMap::new(StorageKey::AccountStreams),
+                    received_streams: LookupMap::new(StorageKey::ReceivedStreams),
+                    next_stream_id: 0,
+                    escrows: LookupMap::new(StorageKey::Escrows),
+                    account_escrows: LookupMap::new(StorageKey::AccountEscrows),
+                    received_escrows: LookupMap::new(StorageKey::ReceivedEscrows),
+                    arbitrated_escrows: LookupMap::new(StorageKey::ArbitratedEscrows),
+                    next_escrow_id: 0,
+                };
+                contract.measure_account_storage_usage();
+                contract
+            }
+            _ => env::panic_str(&format!(
+                "Cannot migrate from the unknown state version {}",
+                state_version
+            )),
+        };
+        write_state_version();
+        contract
+    }
+
+    /// Returns the version of the contract state layout.
+    pub fn state_version(&self) -> u32 {
+        read_state_version()
+    }
+}
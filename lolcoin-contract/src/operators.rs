@@ -0,0 +1,270 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId, Balance};
+
+use crate::events::{
+    OwnerChanged, RewardOperatorAdded, RewardOperatorLimitsUpdated, RewardOperatorRemoved,
+};
+use crate::{Contract, ContractExt};
+
+const NANOSECONDS_PER_HOUR: u64 = 3_600 * 1_000_000_000;
+const HOURS_PER_DAY: u64 = 24;
+const HOURS_PER_WEEK: u64 = 7 * HOURS_PER_DAY;
+
+/// Limits on the amount of tokens a reward operator can mint. `None` means "unlimited".
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardOperatorLimits {
+    /// Maximum amount of a single reward entry.
+    pub max_reward_amount: Option<U128>,
+    /// Maximum total amount of a single `reward` call.
+    pub max_call_amount: Option<U128>,
+    /// Maximum amount minted over the last 24 hours.
+    pub daily_budget: Option<U128>,
+    /// Maximum amount minted over the last 7 days.
+    pub weekly_budget: Option<U128>,
+}
+
+/// The limits of a reward operator along with the amounts minted within the rolling windows.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct RewardOperatorQuota {
+    limits: RewardOperatorLimits,
+    /// Amounts minted over the last week aggregated per hour: (hours since epoch, amount).
+    recent_mints: Vec<(u64, Balance)>,
+}
+
+impl RewardOperatorLimits {
+    /// Checks the largest reward entry and the total amount of a call against the limits.
+    fn check_amounts(
+        &self,
+        largest_amount: Balance,
+        call_amount: Balance,
+    ) -> Result<(), &'static str> {
+        if let Some(max_reward_amount) = self.max_reward_amount {
+            if largest_amount > max_reward_amount.0 {
+                return Err("The reward amount exceeds the operator limit");
+            }
+        }
+        if let Some(max_call_amount) = self.max_call_amount {
+            if call_amount > max_call_amount.0 {
+                return Err("The total reward amount exceeds the operator limit per call");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RewardOperatorQuota {
+    fn new(limits: RewardOperatorLimits) -> Self {
+        Self {
+            limits,
+            recent_mints: vec![],
+        }
+    }
+
+    fn minted_within(&self, current_hour: u64, hours: u64) -> Balance {
+        self.recent_mints
+            .iter()
+            .filter(|(hour, _)| hour + hours > current_hour)
+            .map(|(_, amount)| amount)
+            .sum()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardOperatorQuotaView {
+    pub limits: RewardOperatorLimits,
+    pub minted_last_day: U128,
+    pub minted_last_week: U128,
+}
+
+impl Contract {
+    /// Checks the given mint amounts against the limits of the reward operator and records them
+    /// in the operator's rolling budget. Operators without configured limits are unlimited.
+    pub(crate) fn internal_charge_reward_quota(
+        &mut self,
+        operator_id: &AccountId,
+        amounts: &[Balance],
+    ) {
+        let largest_amount = amounts.iter().copied().max().unwrap_or(0);
+        let call_amount = amounts
+            .iter()
+            .try_fold(0, |total: Balance, amount| total.checked_add(*amount))
+            .unwrap_or_else(|| env::panic_str("Reward amount overflow"));
+        if let Err(error) =
+            self.internal_try_charge_reward_quota(operator_id, largest_amount, call_amount)
+        {
+            env::panic_str(error);
+        }
+    }
+
+    /// Same as `internal_charge_reward_quota`, but takes the largest and the total of the mint
+    /// amounts and returns the error instead of panicking.
+    pub(crate) fn internal_try_charge_reward_quota(
+        &mut self,
+        operator_id: &AccountId,
+        largest_amount: Balance,
+        call_amount: Balance,
+    ) -> Result<(), &'static str> {
+        let Some(mut quota) = self.reward_operator_quotas.get(operator_id) else {
+            return Ok(());
+        };
+        quota.limits.check_amounts(largest_amount, call_amount)?;
+
+        let current_hour = env::block_timestamp() / NANOSECONDS_PER_HOUR;
+        quota
+            .recent_mints
+            .retain(|(hour, _)| hour + HOURS_PER_WEEK > current_hour);
+        if let Some(daily_budget) = quota.limits.daily_budget {
+            if quota.minted_within(current_hour, HOURS_PER_DAY) + call_amount > daily_budget.0 {
+                return Err("The operator daily budget is exhausted");
+            }
+        }
+        if let Some(weekly_budget) = quota.limits.weekly_budget {
+            if quota.minted_within(current_hour, HOURS_PER_WEEK) + call_amount > weekly_budget.0 {
+                return Err("The operator weekly budget is exhausted");
+            }
+        }
+        match quota.recent_mints.last_mut() {
+            Some((hour, amount)) if *hour == current_hour => *amount += call_amount,
+            _ => quota.recent_mints.push((current_hour, call_amount)),
+        }
+        self.reward_operator_quotas.insert(operator_id, &quota);
+        Ok(())
+    }
+
+    /// Returns how many rewards of `amount` fit into the total amount of a single call of the
+    /// reward operator.
+    pub(crate) fn internal_max_rewards_per_call(
+        &self,
+        operator_id: &AccountId,
+        amount: Balance,
+    ) -> Balance {
+        self.reward_operator_quotas
+            .get(operator_id)
+            .and_then(|quota| quota.limits.max_call_amount)
+            .map_or(Balance::MAX, |max_call_amount| max_call_amount.0 / amount)
+    }
+
+    /// Asserts that a single reward of `amount` is within the limits of the reward operator, e.g.
+    /// before scheduling it.
+    pub(crate) fn internal_assert_reward_amount_within_limits(
+        &self,
+        operator_id: &AccountId,
+        amount: Balance,
+    ) {
+        if let Some(quota) = self.reward_operator_quotas.get(operator_id) {
+            if let Err(error) = quota.limits.check_amounts(amount, amount) {
+                env::panic_str(error);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn owner_id(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    /// Transfers the contract ownership (the right to manage reward operators) to
+    /// `new_owner_id`. Can only be called by the current owner.
+    pub fn set_owner(&mut self, new_owner_id: AccountId) {
+        self.assert_owner();
+        if new_owner_id == self.owner_id {
+            return;
+        }
+        let old_owner_id = std::mem::replace(&mut self.owner_id, new_owner_id);
+        OwnerChanged {
+            old_owner_id: &old_owner_id,
+            new_owner_id: &self.owner_id,
+        }
+        .emit();
+    }
+
+    /// Returns the list of accounts allowed to call `reward` and `custodial_ft_transfer`.
+    pub fn reward_operators(&self) -> Vec<AccountId> {
+        let mut reward_operators: Vec<AccountId> = self.reward_operators.iter().cloned().collect();
+        reward_operators.sort();
+        reward_operators
+    }
+
+    /// Grants the reward operator role to `account_id`. Can only be called by the owner.
+    ///
+    /// Returns `false` if the account is already a reward operator.
+    pub fn add_reward_operator(&mut self, account_id: AccountId) -> bool {
+        self.assert_owner();
+        if !self.reward_operators.insert(account_id.clone()) {
+            return false;
+        }
+        RewardOperatorAdded {
+            operator_id: &account_id,
+        }
+        .emit();
+        true
+    }
+
+    /// Revokes the reward operator role from `account_id`. The limits are kept, so they apply
+    /// again if the role is granted back. Can only be called by the owner.
+    ///
+    /// Returns `false` if the account is not a reward operator.
+    pub fn remove_reward_operator(&mut self, account_id: AccountId) -> bool {
+        self.assert_owner();
+        if !self.reward_operators.remove(&account_id) {
+            return false;
+        }
+        RewardOperatorRemoved {
+            operator_id: &account_id,
+        }
+        .emit();
+        true
+    }
+
+    /// Sets the minting limits of the reward operator `account_id`, or lifts them if `limits` is
+    /// `None`. Can only be called by the owner.
+    pub fn set_reward_operator_limits(
+        &mut self,
+        account_id: AccountId,
+        limits: Option<RewardOperatorLimits>,
+    ) {
+        self.assert_owner();
+        require!(
+            self.reward_operators.contains(&account_id),
+            "The account is not a reward operator"
+        );
+        match &limits {
+            Some(limits) => {
+                let quota = match self.reward_operator_quotas.get(&account_id) {
+                    Some(quota) => RewardOperatorQuota {
+                        limits: limits.clone(),
+                        ..quota
+                    },
+                    None => RewardOperatorQuota::new(limits.clone()),
+                };
+                self.reward_operator_quotas.insert(&account_id, &quota);
+            }
+            None => {
+                self.reward_operator_quotas.remove(&account_id);
+            }
+        }
+        RewardOperatorLimitsUpdated {
+            operator_id: &account_id,
+            limits: limits.as_ref(),
+        }
+        .emit();
+    }
+
+    /// Returns the minting limits of the reward operator along with the amounts minted within the
+    /// rolling windows, or `None` if the operator is unlimited.
+    pub fn reward_operator_limits(&self, account_id: AccountId) -> Option<RewardOperatorQuotaView> {
+        let quota = self.reward_operator_quotas.get(&account_id)?;
+        let current_hour = env::block_timestamp() / NANOSECONDS_PER_HOUR;
+        Some(RewardOperatorQuotaView {
+            minted_last_day: quota.minted_within(current_hour, HOURS_PER_DAY).into(),
+            minted_last_week: quota.minted_within(current_hour, HOURS_PER_WEEK).into(),
+            limits: quota.limits,
+        })
+    }
+}
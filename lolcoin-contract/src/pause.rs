@@ -0,0 +1,76 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::near_bindgen;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::events::PauseFlagsUpdated;
+use crate::{Contract, ContractExt};
+
+/// Emergency switches of the contract. `all` pauses every method that moves tokens or NEAR,
+/// including the storage management, except the owner administration and the resolution of
+/// in-flight `ft_transfer_call`s. Voting in polls, rejecting reward proposals, pausing streams and
+/// settling the closed seasons (which only applies the conversion set by the owner) are never
+/// paused.
+#[derive(
+    BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseFlags {
+    pub all: bool,
+    /// Pauses transfers and burns initiated by the holders.
+    pub transfers: bool,
+    /// Pauses minting by the reward operators.
+    pub rewards: bool,
+    /// Pauses movements of the holders' funds by the reward operators.
+    pub custodial_transfers: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum PausableFeature {
+    Transfers,
+    Rewards,
+    CustodialTransfers,
+    Storage,
+}
+
+impl PauseFlags {
+    pub(crate) fn is_paused(&self, feature: PausableFeature) -> bool {
+        self.all
+            || match feature {
+                PausableFeature::Transfers => self.transfers,
+                PausableFeature::Rewards => self.rewards,
+                PausableFeature::CustodialTransfers => self.custodial_transfers,
+                PausableFeature::Storage => false,
+            }
+    }
+}
+
+impl Contract {
+    /// Asserts that the given feature is not paused
+    pub(crate) fn assert_not_paused(&self, feature: PausableFeature) {
+        if self.pause_flags.is_paused(feature) {
+            near_sdk::env::panic_str(match feature {
+                PausableFeature::Transfers => "Transfers are paused",
+                PausableFeature::Rewards => "Rewards are paused",
+                PausableFeature::CustodialTransfers => "Custodial transfers are paused",
+                PausableFeature::Storage => "Storage management is paused",
+            });
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn pause_flags(&self) -> PauseFlags {
+        self.pause_flags
+    }
+
+    /// Replaces the pause flags of the contract. Can only be called by the owner.
+    pub fn set_pause_flags(&mut self, pause_flags: PauseFlags) {
+        self.assert_owner();
+        self.pause_flags = pause_flags;
+        PauseFlagsUpdated {
+            pause_flags: &self.pause_flags,
+        }
+        .emit();
+    }
+}
@@ -0,0 +1,164 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::events::{PollCreated, PollVoted};
+use crate::fungible_token::snapshots::SnapshotId;
+use crate::{Contract, ContractExt};
+
+const MAX_POLL_OPTIONS: usize = 16;
+const MAX_POLL_QUESTION_LENGTH: usize = 256;
+const MAX_POLL_OPTION_LENGTH: usize = 64;
+
+/// A poll the holders vote in with the weight of their balance at the poll snapshot.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Poll {
+    pub id: U64,
+    pub creator_id: AccountId,
+    pub question: String,
+    pub options: Vec<String>,
+    /// The snapshot of the balances the votes are weighted by.
+    pub snapshot_id: SnapshotId,
+    /// The votes are accepted until this block timestamp (in nanoseconds).
+    pub deadline: U64,
+    /// The total weight of the votes per option.
+    pub tallies: Vec<U128>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PollVote {
+    pub option_index: u32,
+    pub weight: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Creates a poll open until `deadline` (a block timestamp in nanoseconds). The votes are
+    /// weighted by the balances at `snapshot_id` (the latest snapshot taken by the owner by
+    /// default), so the tokens cannot be moved around to vote twice. Can only be called by reward
+    /// operators.
+    ///
+    /// Returns the id of the poll.
+    pub fn create_poll(
+        &mut self,
+        question: String,
+        options: Vec<String>,
+        deadline: U64,
+        snapshot_id: Option<SnapshotId>,
+    ) -> U64 {
+        self.assert_reward_operator();
+        require!(
+            (2..=MAX_POLL_OPTIONS).contains(&options.len()),
+            "A poll should have from 2 to 16 options"
+        );
+        require!(
+            question.len() <= MAX_POLL_QUESTION_LENGTH,
+            "The question is too long"
+        );
+        require!(
+            options
+                .iter()
+                .all(|option| option.len() <= MAX_POLL_OPTION_LENGTH),
+            "The option is too long"
+        );
+        require!(
+            deadline.0 > env::block_timestamp(),
+            "The deadline should be in the future"
+        );
+        let snapshot_id = match snapshot_id {
+            Some(snapshot_id) => {
+                self.token.internal_unwrap_snapshot(snapshot_id);
+                snapshot_id
+            }
+            None => self
+                .token
+                .current_snapshot_id()
+                .unwrap_or_else(|| env::panic_str("No snapshot has been taken yet")),
+        };
+        let poll = Poll {
+            id: self.polls.len().into(),
+            creator_id: env::predecessor_account_id(),
+            question,
+            tallies: vec![0.into(); options.len()],
+            options,
+            snapshot_id,
+            deadline,
+        };
+        self.polls.push(&poll);
+        PollCreated { poll: &poll }.emit();
+        poll.id
+    }
+
+    /// Votes for the option of the poll with the weight of the caller's balance at the poll
+    /// snapshot. The vote can be changed until the deadline.
+    pub fn vote(&mut self, poll_id: U64, option_index: u32) {
+        let mut poll = self.internal_unwrap_poll(poll_id);
+        require!(
+            env::block_timestamp() <= poll.deadline.0,
+            "The poll is closed"
+        );
+        require!(
+            (option_index as usize) < poll.options.len(),
+            "The option does not exist"
+        );
+        let voter_id = env::predecessor_account_id();
+        let weight = self
+            .token
+            .internal_balance_of_at(&voter_id, poll.snapshot_id);
+        require!(weight > 0, "The account had no tokens at the poll snapshot");
+
+        let key = (poll_id.0, voter_id.clone());
+        if let Some(previous_vote) = self.poll_votes.get(&key) {
+            let tally = &mut poll.tallies[previous_vote.option_index as usize];
+            *tally = (tally.0 - previous_vote.weight.0).into();
+        }
+        let tally = &mut poll.tallies[option_index as usize];
+        *tally = (tally.0 + weight).into();
+        let vote = PollVote {
+            option_index,
+            weight: weight.into(),
+        };
+        self.poll_votes.insert(&key, &vote);
+        self.polls.replace(poll_id.0, &poll);
+        PollVoted {
+            poll_id,
+            voter_id: &voter_id,
+            option_index,
+            weight: &vote.weight,
+        }
+        .emit();
+    }
+
+    /// Returns the poll along with the current results.
+    pub fn poll(&self, poll_id: U64) -> Option<Poll> {
+        self.polls.get(poll_id.0)
+    }
+
+    /// Returns the polls in the creation order, starting from `from_index` (0 by default) and
+    /// returning at most `limit` entries (all by default).
+    pub fn polls(&self, from_index: Option<U64>, limit: Option<u64>) -> Vec<Poll> {
+        require!(limit != Some(0), "Cannot provide limit of 0.");
+        let start = from_index.map_or(0, u64::from);
+        let end = start
+            .saturating_add(limit.unwrap_or(u64::MAX))
+            .min(self.polls.len());
+        (start..end)
+            .map(|index| self.polls.get(index).unwrap())
+            .collect()
+    }
+
+    pub fn poll_vote(&self, poll_id: U64, account_id: AccountId) -> Option<PollVote> {
+        self.poll_votes.get(&(poll_id.0, account_id))
+    }
+}
+
+impl Contract {
+    fn internal_unwrap_poll(&self, poll_id: U64) -> Poll {
+        self.polls
+            .get(poll_id.0)
+            .unwrap_or_else(|| env::panic_str("The poll does not exist"))
+    }
+}
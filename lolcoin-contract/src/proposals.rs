@@ -0,0 +1,249 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId, Balance};
+
+use crate::events::{
+    MultisigPolicyUpdated, RewardProposalApproved, RewardProposalCreated, RewardProposalFinalized,
+    RewardProposalRejected,
+};
+use crate::pause::PausableFeature;
+use crate::{Contract, ContractExt, Reward};
+
+/// Rewards with the total amount above `threshold` have to be approved by `required_approvals`
+/// reward operators (including the proposer) within `proposal_duration` nanoseconds.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MultisigPolicy {
+    pub threshold: U128,
+    pub required_approvals: u32,
+    pub proposal_duration: U64,
+}
+
+/// A pending `reward` call above the multisig threshold.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardProposal {
+    pub id: U64,
+    pub proposer_id: AccountId,
+    pub rewards: Vec<Reward>,
+    pub memo: Option<String>,
+    pub batch_id: Option<String>,
+    pub total_amount: U128,
+    /// The number of approvals required when the proposal was created.
+    pub required_approvals: u32,
+    pub approvals: Vec<AccountId>,
+    pub rejections: Vec<AccountId>,
+    pub expires_at: U64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalOutcome {
+    Executed,
+    Rejected,
+    Expired,
+}
+
+impl Contract {
+    /// Returns whether the rewards with the given total amount have to be approved by other
+    /// reward operators.
+    pub(crate) fn internal_requires_multisig(&self, total_amount: Balance) -> bool {
+        matches!(&self.multisig_policy, Some(policy)
+            if total_amount > policy.threshold.0 && policy.required_approvals > 1)
+    }
+
+    pub(crate) fn internal_create_reward_proposal(
+        &mut self,
+        rewards: Vec<Reward>,
+        memo: Option<String>,
+        batch_id: Option<String>,
+        total_amount: Balance,
+    ) -> U64 {
+        let policy = self.multisig_policy.as_ref().unwrap();
+        let proposer_id = env::predecessor_account_id();
+        let proposal = RewardProposal {
+            id: self.next_reward_proposal_id.into(),
+            proposer_id: proposer_id.clone(),
+            rewards,
+            memo,
+            batch_id,
+            total_amount: total_amount.into(),
+            required_approvals: policy.required_approvals,
+            approvals: vec![proposer_id],
+            rejections: vec![],
+            expires_at: (env::block_timestamp() + policy.proposal_duration.0).into(),
+        };
+        self.next_reward_proposal_id += 1;
+        self.reward_proposals.insert(&proposal.id.0, &proposal);
+        RewardProposalCreated {
+            proposal_id: proposal.id,
+            proposer_id: &proposal.proposer_id,
+            total_amount: &proposal.total_amount,
+            expires_at: proposal.expires_at,
+        }
+        .emit();
+        proposal.id
+    }
+
+    /// Returns the number of the given votes cast by the current reward operators.
+    fn internal_count_votes(&self, votes: &[AccountId]) -> usize {
+        votes
+            .iter()
+            .filter(|operator_id| self.reward_operators.contains(*operator_id))
+            .count()
+    }
+
+    /// Removes the proposal, executing it or releasing its batch id depending on the outcome.
+    fn internal_finalize_reward_proposal(
+        &mut self,
+        proposal: RewardProposal,
+        outcome: ProposalOutcome,
+    ) {
+        self.reward_proposals.remove(&proposal.id.0);
+        match outcome {
+            ProposalOutcome::Executed => self.internal_reward(
+                &proposal.proposer_id,
+                &proposal.rewards,
+                proposal.memo.as_deref(),
+            ),
+            ProposalOutcome::Rejected | ProposalOutcome::Expired => {
+                if let Some(batch_id) = &proposal.batch_id {
+                    self.applied_reward_batches.remove(batch_id);
+                }
+            }
+        }
+        RewardProposalFinalized {
+            proposal_id: proposal.id,
+            outcome: &outcome,
+        }
+        .emit();
+    }
+
+    /// Returns the proposal unless it has expired, in which case it is finalized.
+    fn internal_take_active_reward_proposal(&mut self, proposal_id: U64) -> Option<RewardProposal> {
+        let proposal = self
+            .reward_proposals
+            .get(&proposal_id.0)
+            .unwrap_or_else(|| env::panic_str("The proposal does not exist"));
+        if env::block_timestamp() > proposal.expires_at.0 {
+            self.internal_finalize_reward_proposal(proposal, ProposalOutcome::Expired);
+            return None;
+        }
+        Some(proposal)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the policy for approving large rewards, or lifts it if `policy` is `None`. Can only be
+    /// called by the owner. The pending proposals keep the number of required approvals.
+    pub fn set_multisig_policy(&mut self, policy: Option<MultisigPolicy>) {
+        self.assert_owner();
+        if let Some(policy) = &policy {
+            require!(
+                policy.required_approvals > 0
+                    && policy.required_approvals as usize <= self.reward_operators.len(),
+                "The number of required approvals should be between 1 and the number of reward operators"
+            );
+        }
+        self.multisig_policy = policy;
+        MultisigPolicyUpdated {
+            policy: self.multisig_policy.as_ref(),
+        }
+        .emit();
+    }
+
+    pub fn multisig_policy(&self) -> Option<MultisigPolicy> {
+        self.multisig_policy.clone()
+    }
+
+    /// Approves the reward proposal, executing it once the required number of approvals is
+    /// reached. Can only be called by reward operators.
+    ///
+    /// Returns whether the proposal was executed. An expired proposal is discarded instead.
+    pub fn approve_proposal(&mut self, proposal_id: U64) -> bool {
+        self.assert_reward_operator();
+        self.assert_not_paused(PausableFeature::Rewards);
+        let Some(mut proposal) = self.internal_take_active_reward_proposal(proposal_id) else {
+            return false;
+        };
+        let operator_id = env::predecessor_account_id();
+        require!(
+            !proposal.approvals.contains(&operator_id),
+            "The proposal is already approved by the operator"
+        );
+        proposal
+            .rejections
+            .retain(|account_id| account_id != &operator_id);
+        proposal.approvals.push(operator_id);
+        RewardProposalApproved {
+            proposal_id,
+            operator_id: proposal.approvals.last().unwrap(),
+        }
+        .emit();
+        if self.internal_count_votes(&proposal.approvals) >= proposal.required_approvals as usize {
+            self.internal_finalize_reward_proposal(proposal, ProposalOutcome::Executed);
+            return true;
+        }
+        self.reward_proposals.insert(&proposal_id.0, &proposal);
+        false
+    }
+
+    /// Rejects the reward proposal, discarding it once the required number of approvals cannot
+    /// be reached. Can only be called by reward operators.
+    ///
+    /// Returns whether the proposal was discarded.
+    pub fn reject_proposal(&mut self, proposal_id: U64) -> bool {
+        self.assert_reward_operator();
+        let Some(mut proposal) = self.internal_take_active_reward_proposal(proposal_id) else {
+            return true;
+        };
+        let operator_id = env::predecessor_account_id();
+        require!(
+            !proposal.rejections.contains(&operator_id),
+            "The proposal is already rejected by the operator"
+        );
+        proposal
+            .approvals
+            .retain(|account_id| account_id != &operator_id);
+        proposal.rejections.push(operator_id);
+        RewardProposalRejected {
+            proposal_id,
+            operator_id: proposal.rejections.last().unwrap(),
+        }
+        .emit();
+        let possible_approvals =
+            self.reward_operators.len() - self.internal_count_votes(&proposal.rejections);
+        if possible_approvals < proposal.required_approvals as usize {
+            self.internal_finalize_reward_proposal(proposal, ProposalOutcome::Rejected);
+            return true;
+        }
+        self.reward_proposals.insert(&proposal_id.0, &proposal);
+        false
+    }
+
+    pub fn reward_proposal(&self, proposal_id: U64) -> Option<RewardProposal> {
+        self.reward_proposals.get(&proposal_id.0)
+    }
+
+    /// Returns the pending reward proposals, starting from `from_index` (0 by default) and
+    /// returning at most `limit` entries (all by default). The expired proposals are listed until
+    /// someone votes on them.
+    pub fn reward_proposals(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<RewardProposal> {
+        require!(limit != Some(0), "Cannot provide limit of 0.");
+        let proposals = self.reward_proposals.values_as_vector();
+        let start = from_index.unwrap_or(0);
+        let end = start
+            .saturating_add(limit.unwrap_or(u64::MAX))
+            .min(proposals.len());
+        (start..end)
+            .map(|index| proposals.get(index).unwrap())
+            .collect()
+    }
+}
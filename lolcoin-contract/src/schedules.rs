@@ -0,0 +1,252 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, log, near_bindgen, require, AccountId, Balance};
+
+use crate::events::{RewardScheduleCanceled, RewardScheduleCreated};
+use crate::pause::PausableFeature;
+use crate::{Contract, ContractExt};
+
+/// One hour in nanoseconds.
+const MIN_REWARD_SCHEDULE_PERIOD: u64 = 3_600 * 1_000_000_000;
+/// The most installments of a single schedule minted by one `process_due_payouts` call.
+const MAX_PAYOUTS_PER_SCHEDULE_CALL: u32 = 100;
+
+/// A reward of `amount` paid to the recipient every `period` nanoseconds, starting at
+/// `next_payout_at`. The installments are minted by `process_due_payouts` and count against the
+/// limits of the operator who created the schedule.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardSchedule {
+    pub id: U64,
+    pub operator_id: AccountId,
+    pub recipient_id: AccountId,
+    pub amount: U128,
+    /// Nanoseconds.
+    pub period: U64,
+    /// The block timestamp (in nanoseconds) the next installment is due at.
+    pub next_payout_at: U64,
+    /// `None` means the schedule runs until it is canceled.
+    pub remaining_payouts: Option<u32>,
+    pub memo: Option<String>,
+}
+
+impl RewardSchedule {
+    /// Returns the number of the installments due at the given timestamp.
+    fn due_payouts(&self, timestamp: u64) -> u32 {
+        let Some(elapsed) = timestamp.checked_sub(self.next_payout_at.0) else {
+            return 0;
+        };
+        let due_payouts = u32::try_from(elapsed / self.period.0 + 1).unwrap_or(u32::MAX);
+        self.remaining_payouts
+            .map_or(due_payouts, |remaining_payouts| {
+                due_payouts.min(remaining_payouts)
+            })
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Schedules a reward of `amount` to `recipient_id` every `period` nanoseconds, starting at
+    /// `start_at` (the current block timestamp by default), `payouts_count` times (until canceled
+    /// by default). Can only be called by reward operators.
+    ///
+    /// The period should be at least an hour and the amount within the operator limit per reward.
+    /// The schedules with the total amount over the multisig threshold (including the ones running
+    /// until canceled, while the multisig policy is set) are rejected.
+    ///
+    /// Returns the schedule id.
+    pub fn create_reward_schedule(
+        &mut self,
+        recipient_id: AccountId,
+        amount: U128,
+        period: U64,
+        start_at: Option<U64>,
+        payouts_count: Option<u32>,
+        memo: Option<String>,
+    ) -> U64 {
+        self.assert_reward_operator();
+        self.assert_not_paused(PausableFeature::Rewards);
+        require!(amount.0 > 0, "The amount should be a positive number");
+        require!(
+            period.0 >= MIN_REWARD_SCHEDULE_PERIOD,
+            "The period should be at least an hour"
+        );
+        require!(
+            payouts_count != Some(0),
+            "Cannot provide payouts count of 0."
+        );
+        let operator_id = env::predecessor_account_id();
+        self.internal_assert_reward_amount_within_limits(&operator_id, amount.0);
+        let total_amount = payouts_count.map_or(Balance::MAX, |payouts_count| {
+            amount.0.saturating_mul(payouts_count.into())
+        });
+        require!(
+            !self.internal_requires_multisig(total_amount),
+            "The total amount exceeds the multisig threshold"
+        );
+        self.internal_register_account_if_needed(&recipient_id);
+
+        let schedule = RewardSchedule {
+            id: self.next_reward_schedule_id.into(),
+            operator_id,
+            recipient_id,
+            amount,
+            period,
+            next_payout_at: start_at.unwrap_or_else(|| env::block_timestamp().into()),
+            remaining_payouts: payouts_count,
+            memo,
+        };
+        self.next_reward_schedule_id += 1;
+        self.reward_schedules.insert(&schedule.id.0, &schedule);
+        RewardScheduleCreated {
+            schedule: &schedule,
+        }
+        .emit();
+        schedule.id
+    }
+
+    /// Cancels the reward schedule. Can only be called by the operator who created the schedule or
+    /// by the owner.
+    pub fn cancel_reward_schedule(&mut self, schedule_id: U64) {
+        let schedule = self
+            .reward_schedules
+            .get(&schedule_id.0)
+            .unwrap_or_else(|| env::panic_str("The reward schedule does not exist"));
+        let predecessor_account_id = env::predecessor_account_id();
+        require!(
+            predecessor_account_id == schedule.operator_id
+                || predecessor_account_id == self.owner_id,
+            "Can only be called by the schedule operator or the owner"
+        );
+        self.reward_schedules.remove(&schedule_id.0);
+        RewardScheduleCanceled {
+            schedule_id,
+            recipient_id: &schedule.recipient_id,
+        }
+        .emit();
+    }
+
+    /// Mints the overdue installments of up to `limit` reward schedules (all by default). The
+    /// schedules are visited round-robin across the calls, so a keeper can call it periodically
+    /// with a small limit. Can be called by anyone.
+    ///
+    /// The schedules of the accounts that are no longer reward operators are canceled. At most 100
+    /// installments of a schedule are minted at once, and the installments exceeding the operator
+    /// limits are postponed until the next call. The schedules that cannot be paid out (e.g. the
+    /// recipient cannot be registered again) are skipped and logged.
+    ///
+    /// Returns the number of the minted installments.
+    pub fn process_due_payouts(&mut self, limit: Option<u32>) -> u32 {
+        self.assert_not_paused(PausableFeature::Rewards);
+        let timestamp = env::block_timestamp();
+        let mut minted_payouts = 0;
+        let mut visited_schedules = 0;
+        let limit = limit.unwrap_or(u32::MAX);
+        while visited_schedules < limit {
+            let schedules_count = self.reward_schedules.len();
+            if u64::from(visited_schedules) >= schedules_count {
+                break;
+            }
+            visited_schedules += 1;
+            if self.reward_payouts_cursor >= schedules_count {
+                self.reward_payouts_cursor = 0;
+            }
+            let mut schedule = self
+                .reward_schedules
+                .values_as_vector()
+                .get(self.reward_payouts_cursor)
+                .unwrap();
+            self.reward_payouts_cursor += 1;
+
+            if !self.reward_operators.contains(&schedule.operator_id) {
+                self.reward_schedules.remove(&schedule.id.0);
+                RewardScheduleCanceled {
+                    schedule_id: schedule.id,
+                    recipient_id: &schedule.recipient_id,
+                }
+                .emit();
+                continue;
+            }
+            let max_rewards_per_call = self
+                .internal_max_rewards_per_call(&schedule.operator_id, schedule.amount.0)
+                .try_into()
+                .unwrap_or(u32::MAX);
+            let due_payouts = schedule
+                .due_payouts(timestamp)
+                .min(MAX_PAYOUTS_PER_SCHEDULE_CALL)
+                .min(max_rewards_per_call);
+            if due_payouts == 0 {
+                continue;
+            }
+            let Some(amount) = Balance::from(due_payouts)
+                .checked_mul(schedule.amount.0)
+                .filter(|amount| self.token.total_supply.checked_add(*amount).is_some())
+            else {
+                log!(
+                    "Skipped the reward schedule {}: Reward amount overflow",
+                    schedule.id.0
+                );
+                continue;
+            };
+            if let Err(error) = self.internal_try_register_account_if_needed(
+                &schedule.recipient_id,
+                &schedule.operator_id,
+            ) {
+                log!("Skipped the reward schedule {}: {}", schedule.id.0, error);
+                continue;
+            }
+            if let Err(error) = self.internal_try_charge_reward_quota(
+                &schedule.operator_id,
+                schedule.amount.0,
+                amount,
+            ) {
+                log!("Postponed the reward schedule {}: {}", schedule.id.0, error);
+                continue;
+            }
+            self.token.internal_mint(
+                &schedule.recipient_id,
+                amount,
+                Some(schedule.memo.as_deref().unwrap_or("scheduled reward")),
+            );
+            minted_payouts += due_payouts;
+
+            schedule.remaining_payouts = schedule
+                .remaining_payouts
+                .map(|remaining_payouts| remaining_payouts - due_payouts);
+            if schedule.remaining_payouts == Some(0) {
+                self.reward_schedules.remove(&schedule.id.0);
+            } else {
+                schedule.next_payout_at = schedule
+                    .next_payout_at
+                    .0
+                    .saturating_add(u64::from(due_payouts) * schedule.period.0)
+                    .into();
+                self.reward_schedules.insert(&schedule.id.0, &schedule);
+            }
+        }
+        minted_payouts
+    }
+
+    pub fn reward_schedule(&self, schedule_id: U64) -> Option<RewardSchedule> {
+        self.reward_schedules.get(&schedule_id.0)
+    }
+
+    /// Returns the reward schedules with their upcoming payouts, starting from `from_index` (0 by
+    /// default) and returning at most `limit` entries (all by default).
+    pub fn reward_schedules(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<RewardSchedule> {
+        require!(limit != Some(0), "Cannot provide limit of 0.");
+        let schedules = self.reward_schedules.values_as_vector();
+        let start = from_index.unwrap_or(0);
+        let end = start
+            .saturating_add(limit.unwrap_or(u64::MAX))
+            .min(schedules.len());
+        (start..end)
+            .map(|index| schedules.get(index).unwrap())
+            .collect()
+    }
+}
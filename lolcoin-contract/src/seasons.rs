@@ -0,0 +1,88 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require, AccountId};
+
+use crate::events::SeasonClosed;
+use crate::fungible_token::seasons::{Season, SeasonId};
+use crate::{Contract, ContractExt};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeasonBalance {
+    /// The season the balance was minted in or last converted into.
+    pub season_id: SeasonId,
+    /// The balance as of `season_id`.
+    pub balance: U128,
+    /// The balance converted into the current season, as reported by `ft_balance_of`.
+    pub current_season_balance: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Closes the current season and opens the next one. The balances left from the closed season
+    /// are converted at `conversion_rate_bps` (in basis points: 0 expires them, 10000 carries them
+    /// over in full) the next time they are touched or settled with `settle_seasons`, while
+    /// `ft_total_supply` reports the converted supply right away. Can only be called by the owner.
+    ///
+    /// Returns the id of the opened season.
+    pub fn close_season(&mut self, conversion_rate_bps: u32) -> SeasonId {
+        self.assert_owner();
+        let closed_season_id = self.token.current_season_id();
+        let opened_season_id = self.token.internal_close_season(conversion_rate_bps);
+        let closed_season = self.token.internal_unwrap_season(closed_season_id);
+        SeasonClosed {
+            season_id: closed_season_id,
+            conversion_rate_bps,
+            outstanding_supply: &closed_season.outstanding_supply,
+            next_season_id: opened_season_id,
+        }
+        .emit();
+        opened_season_id
+    }
+
+    /// Converts the balances of the given accounts left from the closed seasons into the current
+    /// season, emitting the corresponding `FtBurn` and `FtMint` events.
+    pub fn settle_seasons(&mut self, account_ids: Vec<AccountId>) {
+        for account_id in &account_ids {
+            self.token.internal_settle_season(account_id);
+        }
+    }
+
+    pub fn current_season_id(&self) -> SeasonId {
+        self.token.current_season_id()
+    }
+
+    /// Returns the seasons starting from `from_index` (0 by default), returning at most `limit`
+    /// entries (all by default). The `outstanding_supply` of the current season is the balance
+    /// held in it.
+    pub fn seasons(&self, from_index: Option<SeasonId>, limit: Option<u32>) -> Vec<Season> {
+        require!(limit != Some(0), "Cannot provide limit of 0.");
+        let current_season_id = self.token.current_season_id();
+        let from_index = from_index.unwrap_or(0);
+        let end = from_index
+            .saturating_add(limit.unwrap_or(u32::MAX))
+            .min(current_season_id + 1);
+        (from_index..end)
+            .map(|season_id| {
+                let mut season = self.token.internal_unwrap_season(season_id);
+                if season_id == current_season_id {
+                    season.outstanding_supply = self.token.internal_current_season_supply().into();
+                }
+                season
+            })
+            .collect()
+    }
+
+    pub fn ft_season_balance_of(&self, account_id: AccountId) -> Option<SeasonBalance> {
+        let balance = self.token.accounts.get(&account_id)?;
+        let season_id = self.token.internal_account_season_id(&account_id);
+        Some(SeasonBalance {
+            season_id,
+            balance: balance.into(),
+            current_season_balance: self
+                .token
+                .internal_season_converted_balance(balance, season_id)
+                .into(),
+        })
+    }
+}
@@ -0,0 +1,52 @@
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::events::SnapshotTaken;
+use crate::fungible_token::snapshots::{Snapshot, SnapshotId};
+use crate::{Contract, ContractExt};
+
+#[near_bindgen]
+impl Contract {
+    /// Takes a snapshot of the balances, e.g. for a vote weighted by the holdings. Can only be
+    /// called by the owner.
+    ///
+    /// The contract pays for saving the balance of every registered account that changes after
+    /// the snapshot (up to one entry per account and snapshot).
+    ///
+    /// Returns the id of the snapshot.
+    pub fn take_snapshot(&mut self) -> SnapshotId {
+        self.assert_owner();
+        let snapshot_id = self.token.internal_take_snapshot();
+        let snapshot = self.token.internal_unwrap_snapshot(snapshot_id);
+        SnapshotTaken {
+            snapshot_id,
+            total_supply: &snapshot.total_supply,
+        }
+        .emit();
+        snapshot_id
+    }
+
+    /// Returns the id of the latest snapshot, if any.
+    pub fn current_snapshot_id(&self) -> Option<SnapshotId> {
+        self.token.current_snapshot_id()
+    }
+
+    pub fn snapshot(&self, snapshot_id: SnapshotId) -> Option<Snapshot> {
+        self.token.snapshots.get(u64::from(snapshot_id))
+    }
+
+    /// Returns the balance of the account at the time the snapshot was taken.
+    pub fn ft_balance_of_at(&self, account_id: AccountId, snapshot_id: SnapshotId) -> U128 {
+        self.token
+            .internal_balance_of_at(&account_id, snapshot_id)
+            .into()
+    }
+
+    /// Returns the total supply at the time the snapshot was taken, with the balances left from
+    /// the closed seasons converted as in `ft_total_supply`.
+    pub fn ft_total_supply_at(&self, snapshot_id: SnapshotId) -> U128 {
+        self.token
+            .internal_unwrap_snapshot(snapshot_id)
+            .total_supply
+    }
+}
@@ -0,0 +1,214 @@
+//! Ledger of who paid for the storage of each registered account.
+//!
+//! The accounts registered with `storage_deposit` are paid for by the caller. The accounts
+//! registered by reward operators (`reward`, `create_vesting_grant`) are paid for out of the
+//! operator's sponsorship deposit if it covers the storage, or by the contract pool otherwise.
+//! When the account is unregistered, the storage deposit goes back to whoever paid for it.
+//!
+//! The accounts registered before the ledger was introduced were mostly registered by `reward`
+//! without any deposit, so their storage is treated as paid for by the contract and is not
+//! refunded.
+use near_contract_standards::storage_management::{StorageBalance, StorageManagement};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{assert_one_yocto, env, near_bindgen, require, AccountId, Balance, Promise};
+
+use crate::events::{StorageSponsored, StorageSponsorshipPoolUpdated};
+use crate::{Contract, ContractExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum StorageSponsor {
+    /// The storage is paid for by the contract account.
+    Pool,
+    /// The storage is paid for out of the reward operator's sponsorship deposit.
+    Operator(AccountId),
+    /// The storage deposit was attached to `storage_deposit` by the account.
+    User(AccountId),
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageSponsorshipPool {
+    /// The number of the registered accounts sponsored by the pool.
+    pub sponsored_registrations: u64,
+    /// `None` means "unlimited".
+    pub max_sponsored_registrations: Option<u64>,
+}
+
+impl Contract {
+    /// Measures the storage of a registered account, including its entry in the sponsorship
+    /// ledger.
+    pub(crate) fn measure_account_storage_usage(&mut self) {
+        self.token.measure_account_storage_usage();
+        let initial_storage_usage = env::storage_usage();
+        let tmp_account_id = AccountId::new_unchecked("a".repeat(64));
+        self.storage_sponsors.insert(
+            &tmp_account_id,
+            &StorageSponsor::User(tmp_account_id.clone()),
+        );
+        self.token.account_storage_usage += env::storage_usage() - initial_storage_usage;
+        self.storage_sponsors.remove(&tmp_account_id);
+    }
+
+    /// Returns the storage balance of the account. The storage deposit counts only if it goes back
+    /// to the account on unregistration, i.e. not if the account was sponsored by someone else or
+    /// was registered before the sponsorship ledger was introduced.
+    pub(crate) fn internal_storage_balance_of(
+        &self,
+        account_id: &AccountId,
+    ) -> Option<StorageBalance> {
+        let mut storage_balance = self.token.storage_balance_of(account_id.clone())?;
+        match self.storage_sponsors.get(account_id) {
+            Some(StorageSponsor::User(payer_id)) if payer_id == *account_id => {}
+            _ => storage_balance.total = 0.into(),
+        }
+        Some(storage_balance)
+    }
+
+    /// Takes the storage deposit of an account registered by the reward operator out of the
+    /// operator's sponsorship deposit if it covers the storage, or out of the contract pool
+    /// otherwise.
+    pub(crate) fn internal_try_take_operator_sponsorship(
+        &mut self,
+        operator_id: &AccountId,
+    ) -> Result<StorageSponsor, &'static str> {
+        let storage_deposit = self.token.storage_balance_bounds().min.0;
+        let operator_deposit = self.operator_storage_deposits.get(operator_id).unwrap_or(0);
+        if operator_deposit >= storage_deposit {
+            self.operator_storage_deposits
+                .insert(operator_id, &(operator_deposit - storage_deposit));
+            return Ok(StorageSponsor::Operator(operator_id.clone()));
+        }
+        if matches!(self.max_pool_sponsored_registrations,
+            Some(limit) if self.pool_sponsored_registrations >= limit)
+        {
+            return Err("The storage sponsorship pool is exhausted");
+        }
+        self.pool_sponsored_registrations += 1;
+        Ok(StorageSponsor::Pool)
+    }
+
+    /// Records who pays for the storage of the account just registered.
+    pub(crate) fn internal_record_storage_sponsor(
+        &mut self,
+        account_id: &AccountId,
+        sponsor: StorageSponsor,
+    ) {
+        self.storage_sponsors.insert(account_id, &sponsor);
+        StorageSponsored {
+            account_id,
+            sponsor: &sponsor,
+        }
+        .emit();
+    }
+
+    /// Returns the storage deposit of the unregistered account to whoever paid for it, along with
+    /// the attached 1 yoctoNEAR.
+    pub(crate) fn internal_refund_storage_deposit(&mut self, account_id: &AccountId) {
+        let storage_deposit = self.token.storage_balance_bounds().min.0;
+        let (payer_id, refund) = match self.storage_sponsors.remove(account_id) {
+            Some(StorageSponsor::Pool) => {
+                self.pool_sponsored_registrations -= 1;
+                (account_id.clone(), 0)
+            }
+            Some(StorageSponsor::Operator(operator_id)) => {
+                let operator_deposit = self
+                    .operator_storage_deposits
+                    .get(&operator_id)
+                    .unwrap_or(0);
+                self.operator_storage_deposits
+                    .insert(&operator_id, &(operator_deposit + storage_deposit));
+                (account_id.clone(), 0)
+            }
+            Some(StorageSponsor::User(payer_id)) => (payer_id, storage_deposit),
+            // The accounts registered before the ledger was introduced.
+            None => (account_id.clone(), 0),
+        };
+        if payer_id == *account_id {
+            Promise::new(payer_id).transfer(refund + 1);
+        } else {
+            Promise::new(payer_id).transfer(refund);
+            Promise::new(account_id.clone()).transfer(1);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds the attached deposit to the caller's sponsorship deposit that pays for the storage of
+    /// the accounts the caller registers. Can only be called by reward operators.
+    #[payable]
+    pub fn deposit_storage_sponsorship(&mut self) -> U128 {
+        self.assert_reward_operator();
+        let operator_id = env::predecessor_account_id();
+        let operator_deposit = self
+            .operator_storage_deposits
+            .get(&operator_id)
+            .unwrap_or(0)
+            + env::attached_deposit();
+        self.operator_storage_deposits
+            .insert(&operator_id, &operator_deposit);
+        operator_deposit.into()
+    }
+
+    /// Withdraws `amount` (all by default) of the caller's sponsorship deposit. The deposits
+    /// locked in the storage of the sponsored accounts are returned once the accounts are
+    /// unregistered. Exactly 1 yoctoNEAR must be attached.
+    #[payable]
+    pub fn withdraw_storage_sponsorship(&mut self, amount: Option<U128>) -> U128 {
+        assert_one_yocto();
+        let operator_id = env::predecessor_account_id();
+        let operator_deposit = self
+            .operator_storage_deposits
+            .get(&operator_id)
+            .unwrap_or(0);
+        let amount: Balance = amount.map_or(operator_deposit, |amount| amount.0);
+        require!(
+            amount <= operator_deposit,
+            "The amount exceeds the sponsorship deposit"
+        );
+        if amount == operator_deposit {
+            self.operator_storage_deposits.remove(&operator_id);
+        } else {
+            self.operator_storage_deposits
+                .insert(&operator_id, &(operator_deposit - amount));
+        }
+        Promise::new(operator_id).transfer(amount + 1);
+        amount.into()
+    }
+
+    /// Returns the sponsorship deposit of the operator available for new registrations.
+    pub fn storage_sponsorship_balance_of(&self, account_id: AccountId) -> U128 {
+        self.operator_storage_deposits
+            .get(&account_id)
+            .unwrap_or(0)
+            .into()
+    }
+
+    /// Returns who paid for the storage of the account, or `None` if it is unknown (the account
+    /// is not registered or was registered before the sponsorship ledger was introduced).
+    pub fn storage_sponsor_of(&self, account_id: AccountId) -> Option<StorageSponsor> {
+        self.storage_sponsors.get(&account_id)
+    }
+
+    pub fn storage_sponsorship_pool(&self) -> StorageSponsorshipPool {
+        StorageSponsorshipPool {
+            sponsored_registrations: self.pool_sponsored_registrations,
+            max_sponsored_registrations: self.max_pool_sponsored_registrations,
+        }
+    }
+
+    /// Caps the number of the registered accounts the contract pool pays for, or lifts the cap if
+    /// `limit` is `None`. Can only be called by the owner.
+    pub fn set_max_pool_sponsored_registrations(&mut self, limit: Option<u64>) {
+        self.assert_owner();
+        self.max_pool_sponsored_registrations = limit;
+        StorageSponsorshipPoolUpdated {
+            pool: &self.storage_sponsorship_pool(),
+        }
+        .emit();
+    }
+}
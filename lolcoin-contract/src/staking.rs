@@ -0,0 +1,220 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{assert_one_yocto, env, near_bindgen, require, AccountId, Balance, Timestamp};
+
+use crate::events::{Staked, StakingTermsUpdated, Unstaked};
+use crate::fungible_token::seasons::{SeasonId, FULL_CONVERSION_RATE_BPS};
+use crate::internal::mul_div;
+use crate::pause::PausableFeature;
+use crate::{Contract, ContractExt};
+
+const MAX_STAKES_PER_ACCOUNT: usize = 10;
+
+/// A term the tokens can be staked for, earning `interest_rate_bps` (in basis points) of the
+/// staked amount over the whole term.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakingTerm {
+    /// Nanoseconds.
+    pub duration: U64,
+    pub interest_rate_bps: u32,
+}
+
+/// Tokens locked until `unlocks_at`. The staked tokens are held by the contract account and
+/// transferred back on `unstake`; the interest accrues linearly over the term and is minted when
+/// it is claimed.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Stake {
+    pub id: U64,
+    pub amount: U128,
+    /// The interest for the whole term.
+    pub interest: U128,
+    pub claimed_interest: U128,
+    /// Nanoseconds since the Unix epoch.
+    pub staked_at: U64,
+    pub unlocks_at: U64,
+    /// The season the tokens were staked in.
+    pub season_id: SeasonId,
+}
+
+impl Stake {
+    fn accrued_interest(&self, timestamp: Timestamp) -> Balance {
+        let duration = u128::from(self.unlocks_at.0 - self.staked_at.0);
+        if duration == 0 || timestamp >= self.unlocks_at.0 {
+            return self.interest.0;
+        }
+        let elapsed = u128::from(timestamp.saturating_sub(self.staked_at.0));
+        mul_div(self.interest.0, elapsed, duration)
+    }
+
+    fn claimable_interest(&self, timestamp: Timestamp) -> Balance {
+        self.accrued_interest(timestamp) - self.claimed_interest.0
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakeView {
+    #[serde(flatten)]
+    pub stake: Stake,
+    /// The accrued interest that can be claimed with `claim_staking_rewards`.
+    pub claimable_interest: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Replaces the terms the tokens can be staked for. The existing stakes keep their terms. Can
+    /// only be called by the owner.
+    pub fn set_staking_terms(&mut self, terms: Vec<StakingTerm>) {
+        self.assert_owner();
+        self.staking_terms = terms;
+        StakingTermsUpdated {
+            terms: &self.staking_terms,
+        }
+        .emit();
+    }
+
+    pub fn staking_terms(&self) -> Vec<StakingTerm> {
+        self.staking_terms.clone()
+    }
+
+    /// Locks `amount` of the caller's tokens for the term with the index `term` in
+    /// `staking_terms`. Exactly 1 yoctoNEAR must be attached.
+    ///
+    /// Returns the stake id.
+    #[payable]
+    pub fn stake(&mut self, amount: U128, term: u32) -> U64 {
+        assert_one_yocto();
+        self.assert_not_paused(PausableFeature::Transfers);
+        let account_id = env::predecessor_account_id();
+        let term = self
+            .staking_terms
+            .get(term as usize)
+            .unwrap_or_else(|| env::panic_str("The staking term does not exist"))
+            .clone();
+        let mut stakes = self.stakes.get(&account_id).unwrap_or_default();
+        require!(
+            stakes.len() < MAX_STAKES_PER_ACCOUNT,
+            "Too many active stakes"
+        );
+        self.internal_claim_vested_before_spending(&account_id);
+        self.internal_lock_tokens(&account_id, amount.0, "staking");
+
+        let staked_at = env::block_timestamp();
+        let stake = Stake {
+            id: self.next_stake_id.into(),
+            amount,
+            interest: mul_div(
+                amount.0,
+                term.interest_rate_bps.into(),
+                FULL_CONVERSION_RATE_BPS.into(),
+            )
+            .into(),
+            claimed_interest: 0.into(),
+            staked_at: staked_at.into(),
+            unlocks_at: staked_at.saturating_add(term.duration.0).into(),
+            season_id: self.token.current_season_id(),
+        };
+        self.next_stake_id += 1;
+        self.total_staked += amount.0;
+        stakes.push(stake.clone());
+        self.stakes.insert(&account_id, &stakes);
+        Staked {
+            account_id: &account_id,
+            stake: &stake,
+        }
+        .emit();
+        stake.id
+    }
+
+    /// Mints the interest accrued on all the stakes of the caller to the caller's balance,
+    /// converted from the seasons the tokens were staked in.
+    ///
+    /// Returns the claimed amount.
+    pub fn claim_staking_rewards(&mut self) -> U128 {
+        self.assert_not_paused(PausableFeature::Rewards);
+        let account_id = env::predecessor_account_id();
+        let mut stakes = self.stakes.get(&account_id).unwrap_or_default();
+        let timestamp = env::block_timestamp();
+        let mut claimed_interest: Balance = 0;
+        for stake in stakes.iter_mut() {
+            let claimable_interest = stake.claimable_interest(timestamp);
+            stake.claimed_interest = (stake.claimed_interest.0 + claimable_interest).into();
+            claimed_interest += self
+                .token
+                .internal_season_converted_balance(claimable_interest, stake.season_id);
+        }
+        require!(claimed_interest > 0, "There is no interest to claim");
+        self.stakes.insert(&account_id, &stakes);
+        self.token
+            .internal_mint(&account_id, claimed_interest, Some("staking interest"));
+        claimed_interest.into()
+    }
+
+    /// Unlocks the stake once its term is over, transferring the staked tokens back to the
+    /// caller's balance and minting the unclaimed interest, both converted from the season the
+    /// tokens were staked in.
+    ///
+    /// Returns the unstaked amount, including the interest.
+    pub fn unstake(&mut self, stake_id: U64) -> U128 {
+        self.assert_not_paused(PausableFeature::Transfers);
+        let account_id = env::predecessor_account_id();
+        let mut stakes = self.stakes.get(&account_id).unwrap_or_default();
+        let Some(index) = stakes.iter().position(|stake| stake.id == stake_id) else {
+            env::panic_str("The stake does not exist");
+        };
+        let timestamp = env::block_timestamp();
+        require!(
+            timestamp >= stakes[index].unlocks_at.0,
+            "The stake is still locked"
+        );
+        let stake = stakes.remove(index);
+        if stakes.is_empty() {
+            self.stakes.remove(&account_id);
+        } else {
+            self.stakes.insert(&account_id, &stakes);
+        }
+        self.total_staked -= stake.amount.0;
+
+        let amount =
+            self.internal_unlock_tokens(&account_id, stake.amount.0, stake.season_id, "unstaking");
+        let interest = self.token.internal_season_converted_balance(
+            stake.claimable_interest(timestamp),
+            stake.season_id,
+        );
+        if interest > 0 {
+            self.token
+                .internal_mint(&account_id, interest, Some("staking interest"));
+        }
+        Unstaked {
+            account_id: &account_id,
+            stake_id,
+            amount: &U128(amount),
+            interest: &U128(interest),
+        }
+        .emit();
+        (amount + interest).into()
+    }
+
+    /// Returns the active stakes of the account.
+    pub fn stakes_of(&self, account_id: AccountId) -> Vec<StakeView> {
+        let timestamp = env::block_timestamp();
+        self.stakes
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|stake| StakeView {
+                claimable_interest: stake.claimable_interest(timestamp).into(),
+                stake,
+            })
+            .collect()
+    }
+
+    /// Returns the amount of tokens locked in the stakes, in the seasons they were staked in. The
+    /// staked tokens are included in `ft_total_supply` as the balance of the contract account.
+    pub fn total_staked(&self) -> U128 {
+        self.total_staked.into()
+    }
+}
@@ -0,0 +1,251 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId, Balance, Timestamp};
+
+use crate::events::{StreamCanceled, StreamCreated, StreamUpdated};
+use crate::fungible_token::seasons::SeasonId;
+use crate::internal::{insert_into_index, remove_from_index};
+use crate::pause::PausableFeature;
+use crate::{Contract, ContractExt};
+
+const NANOSECONDS_PER_SECOND: u128 = 1_000_000_000;
+const MAX_STREAMS_PER_ACCOUNT: usize = 20;
+
+/// A continuous payment of `rate_per_sec` tokens per second from the sender to the receiver,
+/// funded with `deposit`. The deposit is transferred from the sender to the contract account when
+/// the stream is created; the streamed tokens are transferred to the receiver when they are
+/// withdrawn, and the rest goes back to the sender when the stream is canceled.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Stream {
+    pub id: U64,
+    pub sender_id: AccountId,
+    pub receiver_id: AccountId,
+    pub rate_per_sec: U128,
+    pub deposit: U128,
+    pub withdrawn: U128,
+    /// Nanoseconds since the Unix epoch.
+    pub started_at: U64,
+    /// Set while the stream is paused.
+    pub paused_at: Option<U64>,
+    /// The total time (in nanoseconds) the stream was paused for.
+    pub paused_duration: U64,
+    /// The season the deposit was made in.
+    pub season_id: SeasonId,
+}
+
+impl Stream {
+    /// Returns the amount streamed to the receiver by the given timestamp.
+    fn streamed_amount(&self, timestamp: Timestamp) -> Balance {
+        let until = self.paused_at.map_or(timestamp, |paused_at| paused_at.0);
+        let elapsed = until
+            .saturating_sub(self.started_at.0)
+            .saturating_sub(self.paused_duration.0);
+        self.rate_per_sec
+            .0
+            .checked_mul(u128::from(elapsed))
+            .map_or(self.deposit.0, |amount| {
+                (amount / NANOSECONDS_PER_SECOND).min(self.deposit.0)
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StreamView {
+    #[serde(flatten)]
+    pub stream: Stream,
+    /// The streamed tokens the receiver can withdraw.
+    pub withdrawable: U128,
+    /// The part of the deposit that is not streamed yet.
+    pub remaining: U128,
+}
+
+impl Contract {
+    fn internal_unwrap_stream(&self, stream_id: U64) -> Stream {
+        self.streams
+            .get(&stream_id.0)
+            .unwrap_or_else(|| env::panic_str("The stream does not exist"))
+    }
+
+    /// Removes the stream, refunding its storage to the sender who paid for it.
+    fn internal_remove_stream(&mut self, stream: &Stream) {
+        let initial_storage_usage = env::storage_usage();
+        self.streams.remove(&stream.id.0);
+        remove_from_index(&mut self.account_streams, &stream.sender_id, stream.id.0);
+        remove_from_index(&mut self.received_streams, &stream.receiver_id, stream.id.0);
+        self.internal_refund_released_storage(&stream.sender_id, initial_storage_usage);
+    }
+
+    /// Transfers the streamed tokens not withdrawn yet to the receiver. The payouts are blocked
+    /// while the sender is frozen.
+    ///
+    /// Returns the amount of the withdrawn tokens as of the stream (before the season conversion).
+    fn internal_withdraw_from_stream(&mut self, stream: &mut Stream) -> Balance {
+        self.token.internal_assert_not_frozen(&stream.sender_id);
+        let amount = stream.streamed_amount(env::block_timestamp()) - stream.withdrawn.0;
+        if amount > 0 {
+            stream.withdrawn = (stream.withdrawn.0 + amount).into();
+            self.internal_unlock_tokens(&stream.receiver_id, amount, stream.season_id, "stream");
+        }
+        amount
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Streams `deposit` of the caller's tokens to `receiver_id` at `rate_per_sec` tokens per
+    /// second, starting now. An account can send up to 20 streams at a time.
+    ///
+    /// The caller must attach a deposit covering the storage of the stream (the unused deposit is
+    /// refunded); the storage is refunded to the caller once the stream is removed.
+    ///
+    /// Returns the stream id.
+    #[payable]
+    pub fn create_stream(
+        &mut self,
+        receiver_id: AccountId,
+        rate_per_sec: U128,
+        deposit: U128,
+    ) -> U64 {
+        require!(
+            env::attached_deposit() > 0,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        self.assert_not_paused(PausableFeature::Transfers);
+        let sender_id = env::predecessor_account_id();
+        require!(
+            sender_id != receiver_id,
+            "Sender and receiver should be different"
+        );
+        require!(rate_per_sec.0 > 0, "The rate should be a positive number");
+        self.token.internal_unwrap_balance_of(&receiver_id);
+        let mut sender_stream_ids = self.account_streams.get(&sender_id).unwrap_or_default();
+        require!(
+            sender_stream_ids.len() < MAX_STREAMS_PER_ACCOUNT,
+            "Too many active streams"
+        );
+        self.internal_claim_vested_before_spending(&sender_id);
+        let initial_storage_usage = env::storage_usage();
+        self.internal_lock_tokens(&sender_id, deposit.0, "stream deposit");
+
+        let stream = Stream {
+            id: self.next_stream_id.into(),
+            sender_id,
+            receiver_id,
+            rate_per_sec,
+            deposit,
+            withdrawn: 0.into(),
+            started_at: env::block_timestamp().into(),
+            paused_at: None,
+            paused_duration: 0.into(),
+            season_id: self.token.current_season_id(),
+        };
+        self.next_stream_id += 1;
+        self.streams.insert(&stream.id.0, &stream);
+        sender_stream_ids.push(stream.id.0);
+        self.account_streams
+            .insert(&stream.sender_id, &sender_stream_ids);
+        insert_into_index(&mut self.received_streams, &stream.receiver_id, stream.id.0);
+        self.internal_charge_storage_deposit(initial_storage_usage);
+        StreamCreated { stream: &stream }.emit();
+        stream.id
+    }
+
+    /// Transfers the tokens streamed so far to the receiver. Can only be called by the receiver. The
+    /// stream is removed once its deposit is fully withdrawn.
+    ///
+    /// Returns the withdrawn amount.
+    pub fn withdraw_from_stream(&mut self, stream_id: U64) -> U128 {
+        self.assert_not_paused(PausableFeature::Transfers);
+        let mut stream = self.internal_unwrap_stream(stream_id);
+        require!(
+            env::predecessor_account_id() == stream.receiver_id,
+            "Can only be called by the stream receiver"
+        );
+        let amount = self.internal_withdraw_from_stream(&mut stream);
+        if stream.withdrawn == stream.deposit {
+            self.internal_remove_stream(&stream);
+        } else {
+            self.streams.insert(&stream_id.0, &stream);
+        }
+        amount.into()
+    }
+
+    /// Pauses or resumes the stream. Can only be called by the sender.
+    pub fn set_stream_paused(&mut self, stream_id: U64, paused: bool) {
+        let mut stream = self.internal_unwrap_stream(stream_id);
+        require!(
+            env::predecessor_account_id() == stream.sender_id,
+            "Can only be called by the stream sender"
+        );
+        let timestamp = env::block_timestamp();
+        match (stream.paused_at, paused) {
+            (None, true) => stream.paused_at = Some(timestamp.into()),
+            (Some(paused_at), false) => {
+                stream.paused_duration =
+                    (stream.paused_duration.0 + timestamp - paused_at.0).into();
+                stream.paused_at = None;
+            }
+            _ => return,
+        }
+        self.streams.insert(&stream_id.0, &stream);
+        StreamUpdated {
+            stream_id,
+            receiver_id: &stream.receiver_id,
+            paused,
+        }
+        .emit();
+    }
+
+    /// Stops the stream, paying out the streamed tokens to the receiver and refunding the rest of
+    /// the deposit to the sender. Can be called by either of them.
+    pub fn cancel_stream(&mut self, stream_id: U64) {
+        self.assert_not_paused(PausableFeature::Transfers);
+        let mut stream = self.internal_unwrap_stream(stream_id);
+        let predecessor_account_id = env::predecessor_account_id();
+        require!(
+            predecessor_account_id == stream.sender_id
+                || predecessor_account_id == stream.receiver_id,
+            "Can only be called by the stream sender or receiver"
+        );
+        let withdrawn_amount = self.internal_withdraw_from_stream(&mut stream);
+        let refund = stream.deposit.0 - stream.withdrawn.0;
+        if refund > 0 {
+            self.internal_unlock_tokens(
+                &stream.sender_id,
+                refund,
+                stream.season_id,
+                "stream refund",
+            );
+        }
+        self.internal_remove_stream(&stream);
+        StreamCanceled {
+            stream_id,
+            withdrawn: &U128(withdrawn_amount),
+            refund: &U128(refund),
+        }
+        .emit();
+    }
+
+    pub fn stream(&self, stream_id: U64) -> Option<StreamView> {
+        let stream = self.streams.get(&stream_id.0)?;
+        let streamed_amount = stream.streamed_amount(env::block_timestamp());
+        Some(StreamView {
+            withdrawable: (streamed_amount - stream.withdrawn.0).into(),
+            remaining: (stream.deposit.0 - streamed_amount).into(),
+            stream,
+        })
+    }
+
+    /// Returns the active streams the account sends or receives.
+    pub fn streams_of(&self, account_id: AccountId) -> Vec<StreamView> {
+        let mut stream_ids = self.account_streams.get(&account_id).unwrap_or_default();
+        stream_ids.extend(self.received_streams.get(&account_id).unwrap_or_default());
+        stream_ids
+            .into_iter()
+            .filter_map(|stream_id| self.stream(stream_id.into()))
+            .collect()
+    }
+}
@@ -0,0 +1,196 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId, Balance, Timestamp};
+
+use crate::events::VestingGrantCreated;
+use crate::internal::mul_div;
+use crate::pause::PausableFeature;
+use crate::{Contract, ContractExt};
+
+/// Tokens awarded to an account that unlock in equal installments: `amount / periods_count` at
+/// the end of every period since `start_timestamp`. The tokens are minted when they are claimed,
+/// but `ft_balance_of` reports them as soon as they are unlocked.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingGrant {
+    pub id: U64,
+    pub amount: U128,
+    pub claimed_amount: U128,
+    /// Nanoseconds since the Unix epoch.
+    pub start_timestamp: U64,
+    /// Nanoseconds.
+    pub period_duration: U64,
+    pub periods_count: u32,
+    pub memo: Option<String>,
+}
+
+impl VestingGrant {
+    fn unlocked_amount(&self, timestamp: Timestamp) -> Balance {
+        let Some(elapsed) = timestamp.checked_sub(self.start_timestamp.0) else {
+            return 0;
+        };
+        let periods_count = u128::from(self.periods_count);
+        let elapsed_periods = u128::from(elapsed / self.period_duration.0).min(periods_count);
+        mul_div(self.amount.0, elapsed_periods, periods_count)
+    }
+
+    fn claimable_amount(&self, timestamp: Timestamp) -> Balance {
+        self.unlocked_amount(timestamp) - self.claimed_amount.0
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingBalance {
+    /// Granted tokens that are not unlocked yet.
+    pub locked: U128,
+    /// Unlocked tokens that can be claimed with `claim_vested`.
+    pub unlocked: U128,
+}
+
+impl Contract {
+    /// Mints the unlocked tokens of all the vesting grants of the account to its balance, removing
+    /// the fully claimed grants.
+    ///
+    /// Returns the claimed amount.
+    pub(crate) fn internal_claim_vested(&mut self, account_id: &AccountId) -> Balance {
+        let Some(mut grants) = self.vesting_grants.get(account_id) else {
+            return 0;
+        };
+        let timestamp = env::block_timestamp();
+        let mut claimed_amount: Balance = 0;
+        for grant in grants.iter_mut() {
+            let claimable_amount = grant.claimable_amount(timestamp);
+            grant.claimed_amount = (grant.claimed_amount.0 + claimable_amount).into();
+            claimed_amount += claimable_amount;
+        }
+        if claimed_amount == 0 {
+            return 0;
+        }
+
+        grants.retain(|grant| grant.claimed_amount != grant.amount);
+        if grants.is_empty() {
+            self.vesting_grants.remove(account_id);
+        } else {
+            self.vesting_grants.insert(account_id, &grants);
+        }
+        self.token
+            .internal_mint(account_id, claimed_amount, Some("vesting"));
+        claimed_amount
+    }
+
+    /// Claims the unlocked vesting tokens of the account before it spends its balance, since
+    /// `ft_balance_of` already reports them. Does nothing while the rewards are paused.
+    pub(crate) fn internal_claim_vested_before_spending(&mut self, account_id: &AccountId) {
+        if !self.pause_flags.is_paused(PausableFeature::Rewards) {
+            self.internal_claim_vested(account_id);
+        }
+    }
+
+    /// Returns the unlocked vesting tokens of the account that are not claimed yet.
+    pub(crate) fn internal_unclaimed_vested_balance_of(&self, account_id: &AccountId) -> Balance {
+        let timestamp = env::block_timestamp();
+        self.vesting_grants
+            .get(account_id)
+            .unwrap_or_default()
+            .iter()
+            .map(|grant| grant.claimable_amount(timestamp))
+            .sum()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Grants `amount` of tokens to `account_id` that unlock in `periods_count` equal installments,
+    /// one every `period_duration` nanoseconds since `start_timestamp` (the current block
+    /// timestamp by default). Can only be called by reward operators.
+    ///
+    /// The grant counts against the operator limits in full and cannot exceed the threshold of the
+    /// multisig policy. Returns the grant id.
+    pub fn create_vesting_grant(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        start_timestamp: Option<U64>,
+        period_duration: U64,
+        periods_count: u32,
+        memo: Option<String>,
+    ) -> U64 {
+        self.assert_reward_operator();
+        self.assert_not_paused(PausableFeature::Rewards);
+        require!(amount.0 > 0, "The amount should be a positive number");
+        require!(
+            period_duration.0 > 0 && periods_count > 0,
+            "The vesting schedule should have at least one non-empty period"
+        );
+        require!(
+            !self.internal_requires_multisig(amount.0),
+            "The amount exceeds the multisig threshold"
+        );
+        self.internal_charge_reward_quota(&env::predecessor_account_id(), &[amount.0]);
+        self.internal_register_account_if_needed(&account_id);
+
+        let grant = VestingGrant {
+            id: self.next_vesting_grant_id.into(),
+            amount,
+            claimed_amount: 0.into(),
+            start_timestamp: start_timestamp.unwrap_or_else(|| env::block_timestamp().into()),
+            period_duration,
+            periods_count,
+            memo,
+        };
+        self.next_vesting_grant_id += 1;
+        let mut grants = self.vesting_grants.get(&account_id).unwrap_or_default();
+        grants.push(grant.clone());
+        self.vesting_grants.insert(&account_id, &grants);
+        VestingGrantCreated {
+            account_id: &account_id,
+            grant: &grant,
+        }
+        .emit();
+        grant.id
+    }
+
+    /// Mints the unlocked tokens of all the vesting grants of the caller to the caller's balance.
+    /// Fully claimed grants are removed.
+    ///
+    /// The unlocked tokens are also claimed automatically when the caller spends its balance.
+    ///
+    /// Returns the claimed amount.
+    pub fn claim_vested(&mut self) -> U128 {
+        self.assert_not_paused(PausableFeature::Rewards);
+        let account_id = env::predecessor_account_id();
+        require!(
+            self.vesting_grants.get(&account_id).is_some(),
+            "The account has no vesting grants"
+        );
+        let claimed_amount = self.internal_claim_vested(&account_id);
+        require!(claimed_amount > 0, "There are no unlocked tokens to claim");
+        claimed_amount.into()
+    }
+
+    pub fn vesting_grants(&self, account_id: AccountId) -> Vec<VestingGrant> {
+        self.vesting_grants.get(&account_id).unwrap_or_default()
+    }
+
+    /// Returns the split of the not yet claimed vesting tokens of the account. Note that
+    /// `ft_balance_of` includes the unlocked tokens.
+    pub fn ft_vesting_balance_of(&self, account_id: AccountId) -> VestingBalance {
+        let timestamp = env::block_timestamp();
+        let (locked, unlocked) =
+            self.vesting_grants(account_id)
+                .iter()
+                .fold((0, 0), |(locked, unlocked), grant| {
+                    let unlocked_amount = grant.unlocked_amount(timestamp);
+                    (
+                        locked + (grant.amount.0 - unlocked_amount),
+                        unlocked + (unlocked_amount - grant.claimed_amount.0),
+                    )
+                });
+        VestingBalance {
+            locked: U128(locked),
+            unlocked: U128(unlocked),
+        }
+    }
+}